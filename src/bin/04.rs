@@ -1,15 +1,29 @@
-#[macro_use]
-extern crate scan_fmt;
+use advent_of_code::helpers::parse::{self, Cursor};
 
-use std::*;
+type Assignment = (u32, u32);
+
+fn parse_range(l: &mut Cursor) -> parse::Result<Assignment> {
+    let start = l.int()?;
+    l.tag("-")?;
+    let end = l.int()?;
+
+    Ok((start, end))
+}
+
+fn parse_input(input: &str) -> parse::Result<Vec<(Assignment, Assignment)>> {
+    parse::each_line(input, |l| {
+        let first = parse_range(l)?;
+        l.tag(",")?;
+        let second = parse_range(l)?;
+
+        Ok((first, second))
+    })
+}
 
 pub fn part_one(input: &str) -> Option<u32> {
     let mut subsets = 0;
 
-    for l in input.lines() {
-        let (start1, end1, start2, end2) =
-            scan_fmt!(l, "{d}-{d},{d}-{d}", u32, u32, u32, u32).unwrap();
-
+    for ((start1, end1), (start2, end2)) in parse_input(input).unwrap() {
         if (start1 <= start2 && end2 <= end1) || (start2 <= start1 && end1 <= end2) {
             subsets += 1;
         }
@@ -21,10 +35,7 @@ pub fn part_one(input: &str) -> Option<u32> {
 pub fn part_two(input: &str) -> Option<u32> {
     let mut overlaps = 0;
 
-    for l in input.lines() {
-        let (start1, end1, start2, end2) =
-            scan_fmt!(l, "{d}-{d},{d}-{d}", u32, u32, u32, u32).unwrap();
-
+    for ((start1, end1), (start2, end2)) in parse_input(input).unwrap() {
         if (start1 <= start2 && start2 <= end1) || (start2 <= start1 && start1 <= end2) {
             overlaps += 1;
         }
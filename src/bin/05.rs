@@ -1,10 +1,9 @@
 #![feature(iter_next_chunk)]
 
-#[macro_use]
-extern crate scan_fmt;
-
 use std::*;
 
+use advent_of_code::helpers::parse::{self, Cursor};
+
 #[derive(Debug)]
 struct Move {
     n: usize,
@@ -18,6 +17,22 @@ struct State {
     moves: Vec<Move>,
 }
 
+fn parse_move(m: &mut Cursor) -> parse::Result<Move> {
+    m.tag("move ")?;
+    let n = m.int()?;
+    m.tag(" from ")?;
+    let from: usize = m.int()?;
+    m.tag(" to ")?;
+    let to: usize = m.int()?;
+    m.end()?;
+
+    Ok(Move {
+        n,
+        from: from - 1,
+        to: to - 1,
+    })
+}
+
 fn parse_input(input: &str) -> State {
     let mut state = State {
         crates: vec![],
@@ -45,13 +60,13 @@ fn parse_input(input: &str) -> State {
         }
     }
 
-    for m in input.lines().skip(n_rows + 2) {
-        let (n, from, to) = scan_fmt!(m, "move {d} from {d} to {d}", usize, usize, usize).unwrap();
-        state.moves.push(Move {
-            n,
-            from: from - 1,
-            to: to - 1,
-        });
+    let mut moves = Cursor::new(input);
+    for _ in 0..n_rows + 2 {
+        moves.line();
+    }
+
+    while let Some(mut m) = moves.line() {
+        state.moves.push(parse_move(&mut m).unwrap());
     }
 
     for s in &mut state.crates {
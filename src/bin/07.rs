@@ -1,126 +1,346 @@
-#![feature(map_try_insert)]
+use std::collections::BTreeMap;
 
-#[macro_use]
-extern crate scan_fmt;
+use advent_of_code::helpers::parse::{self, Cursor};
 
-use std::collections::*;
-use std::*;
+type NodeId = usize;
 
-use itertools::Itertools;
+const ROOT: NodeId = 0;
 
 #[derive(Debug)]
-struct Dir<'a> {
-    subdirs: HashMap<&'a str, Dir<'a>>,
-    files: HashMap<&'a str, u32>,
+enum Kind<'a> {
+    Dir(BTreeMap<&'a str, NodeId>),
+    File,
 }
 
-impl Dir<'_> {
+#[derive(Debug)]
+struct Node<'a> {
+    name: &'a str,
+    parent: Option<NodeId>,
+    /// For a directory, the total size of everything below it.
+    size: u64,
+    kind: Kind<'a>,
+}
+
+/// A file tree stored in an arena, with nodes referring to each other by index.
+///
+/// A node is always created after its parent, so it has a larger id.
+#[derive(Debug)]
+struct FileSystem<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> FileSystem<'a> {
     fn new() -> Self {
         Self {
-            subdirs: HashMap::new(),
-            files: HashMap::new(),
+            nodes: vec![Node {
+                name: "",
+                parent: None,
+                size: 0,
+                kind: Kind::Dir(BTreeMap::new()),
+            }],
         }
     }
-}
 
-fn parse_input(input: &str) -> Dir {
-    let mut lines = input.lines();
-
-    let mut root = Dir::new();
-    let mut current_path = vec![&mut root as *mut Dir];
-
-    loop {
-        match lines.next() {
-            None => break,
-            Some(line) => match line {
-                "$ ls" => (),
-                "$ cd /" => current_path.truncate(1),
-                "$ cd .." => drop(current_path.pop()),
-                line => unsafe {
-                    let &top = current_path.last().unwrap();
-                    match line.strip_prefix("$ cd ") {
-                        Some(dir_name) => {
-                            let next_dir = (*top).subdirs.get_mut(dir_name).unwrap();
-                            current_path.push(next_dir);
-                        }
-                        None => match line.strip_prefix("dir ") {
-                            Some(dir_name) => drop((*top).subdirs.try_insert(dir_name, Dir::new())),
-                            None => {
-                                let (size, name) = line.split_whitespace().collect_tuple().unwrap();
-                                let size = size.parse::<u32>().unwrap();
-
-                                (*top).files.insert(name, size);
-                            }
-                        },
-                    }
-                },
-            },
+    fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id].kind, Kind::Dir(_))
+    }
+
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[dir].kind {
+            Kind::Dir(children) => children.get(name).copied(),
+            Kind::File => None,
+        }
+    }
+
+    fn add_node(&mut self, dir: NodeId, name: &'a str, kind: Kind<'a>) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name,
+            parent: Some(dir),
+            size: 0,
+            kind,
+        });
+
+        let Kind::Dir(children) = &mut self.nodes[dir].kind else {
+            unreachable!("only directories get children");
+        };
+        children.insert(name, id);
+
+        id
+    }
+
+    /// The subdirectory `name` of `dir`, created if it isn't known yet.
+    fn mkdir(&mut self, dir: NodeId, name: &'a str) -> Result<NodeId, String> {
+        match self.child(dir, name) {
+            Some(id) if self.is_dir(id) => Ok(id),
+            Some(id) => Err(format!("{} is a file", self.path(id))),
+            None => Ok(self.add_node(dir, name, Kind::Dir(BTreeMap::new()))),
+        }
+    }
+
+    /// Records the file `name` in `dir`, replacing the size it was listed with before.
+    fn add_file(&mut self, dir: NodeId, name: &'a str, size: u64) -> Result<NodeId, String> {
+        let id = match self.child(dir, name) {
+            Some(id) if self.is_dir(id) => return Err(format!("{} is a directory", self.path(id))),
+            Some(id) => id,
+            None => self.add_node(dir, name, Kind::File),
+        };
+
+        let old_size = self.nodes[id].size;
+        let mut curr = Some(id);
+        while let Some(n) = curr {
+            self.nodes[n].size = self.nodes[n].size - old_size + size;
+            curr = self.nodes[n].parent;
+        }
+
+        Ok(id)
+    }
+
+    /// Follows a `cd` argument from `dir`, which may be absolute and have several components.
+    fn resolve(&mut self, dir: NodeId, path: &'a str) -> Result<NodeId, String> {
+        let (mut curr, rest) = match path.strip_prefix('/') {
+            Some(rest) => (ROOT, rest),
+            None => (dir, path),
+        };
+
+        for name in rest.split('/').filter(|n| !n.is_empty()) {
+            curr = match name {
+                "." => curr,
+                ".." => self.nodes[curr].parent.unwrap_or(ROOT),
+                _ => self.mkdir(curr, name)?,
+            };
         }
+
+        Ok(curr)
+    }
+
+    fn size(&self, id: NodeId) -> u64 {
+        self.nodes[id].size
     }
 
-    root
+    fn find(&self, pred: impl Fn(NodeId) -> bool) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).filter(move |&id| pred(id))
+    }
+
+    fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut curr = id;
+        while let Some(parent) = self.nodes[curr].parent {
+            names.push(self.nodes[curr].name);
+            curr = parent;
+        }
+
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let root = parse_input(input);
+fn parse_line<'a>(
+    fs: &mut FileSystem<'a>,
+    cwd: &mut NodeId,
+    l: &mut Cursor<'a>,
+) -> parse::Result<()> {
+    let at_line = *l;
 
-    fn size(dir: &Dir) -> (u32, u32) {
-        let file_size: u32 = dir.files.iter().map(|(_, s)| s).sum();
-        let (subdir_size, subdir_total) = dir.subdirs.iter().fold((0, 0), |acc, (_, d)| {
-            let (d_size, d_total) = size(d);
-            (acc.0 + d_size, acc.1 + d_total)
-        });
+    let done = if l.eat("$ cd ") {
+        fs.resolve(*cwd, l.token()?).map(|dir| *cwd = dir)
+    } else if l.eat("$ ls") {
+        Ok(())
+    } else if l.eat("dir ") {
+        fs.mkdir(*cwd, l.token()?).map(drop)
+    } else {
+        let size = l.int()?;
+        l.tag(" ")?;
+        fs.add_file(*cwd, l.token()?, size).map(drop)
+    };
+
+    done.map_err(|e| at_line.error(e))
+}
+
+fn parse_input(input: &str) -> FileSystem<'_> {
+    let mut fs = FileSystem::new();
+    let mut cwd = ROOT;
+
+    parse::each_line(input, |l| parse_line(&mut fs, &mut cwd, l)).unwrap();
 
-        let dir_size = file_size + subdir_size;
-        if dir_size <= 100000 {
-            (dir_size, subdir_total + dir_size)
-        } else {
-            (dir_size, subdir_total)
+    fs
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    let fs = parse_input(input);
+
+    Some(
+        fs.find(|id| fs.is_dir(id) && fs.size(id) <= 100000)
+            .map(|d| fs.size(d))
+            .sum(),
+    )
+}
+
+/// How big the disk is and how much free space the update needs.
+struct Disk {
+    capacity: u64,
+    required: u64,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            capacity: 70_000_000,
+            required: 30_000_000,
         }
     }
+}
+
+impl Disk {
+    /// Directories that free up enough space when deleted, smallest first.
+    fn deletion_candidates(&self, fs: &FileSystem) -> Vec<NodeId> {
+        let free = self.capacity.saturating_sub(fs.size(ROOT));
+        let needed = self.required.saturating_sub(free);
 
-    let (_, total) = size(&root);
+        let mut candidates = fs
+            .find(|id| fs.is_dir(id) && fs.size(id) >= needed)
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|&id| (fs.size(id), id));
 
-    Some(total)
+        candidates
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let root = parse_input(input);
+/// A size the way `du -h` prints it: rounded up, with one decimal below 10.
+fn human_size(size: u64) -> String {
+    let mut value = size as f64;
+    let mut units = ["", "K", "M", "G", "T", "P"].into_iter();
+    let mut unit = units.next().unwrap();
 
-    fn size(dir: &Dir) -> u32 {
-        let file_size: u32 = dir.files.iter().map(|(_, s)| s).sum();
-        let subdir_size: u32 = dir.subdirs.iter().map(|(_, s)| size(s)).sum();
-        file_size + subdir_size
+    while value >= 1024.0 {
+        value /= 1024.0;
+        unit = units.next().unwrap();
     }
 
-    let used_size = size(&root);
-    let free_size = 70000000 - used_size;
-    let needed_size = 30000000 - free_size;
+    if unit.is_empty() {
+        size.to_string()
+    } else if value < 10.0 {
+        format!("{:.1}{unit}", (value * 10.0).ceil() / 10.0)
+    } else {
+        format!("{}{unit}", value.ceil())
+    }
+}
 
-    fn find_min(needed_size: u32, dir: &Dir) -> (u32, u32) {
-        let file_size: u32 = dir.files.iter().map(|(_, s)| s).sum();
-        let (subdir_size, subdir_best) = dir.subdirs.iter().fold((0, 70000000), |acc, (_, d)| {
-            let (d_size, d_best) = find_min(needed_size, d);
-            (acc.0 + d_size, cmp::min(acc.1, d_best))
-        });
+fn json_escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_owned(),
+            '\\' => "\\\\".to_owned(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+impl FileSystem<'_> {
+    fn children(&self, dir: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        match &self.nodes[dir].kind {
+            Kind::Dir(children) => Some(children.values().copied()),
+            Kind::File => None,
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    /// A listing like the one `tree` prints, with the size of every node.
+    fn tree(&self) -> String {
+        fn visit(fs: &FileSystem, id: NodeId, prefix: &str, out: &mut String) {
+            let children = fs.children(id).collect::<Vec<_>>();
+            for (i, &child) in children.iter().enumerate() {
+                let last = i + 1 == children.len();
+                let node = &fs.nodes[child];
+                let slash = if fs.is_dir(child) { "/" } else { "" };
+
+                let branch = if last { "└── " } else { "├── " };
+                out.push_str(&format!(
+                    "{prefix}{branch}{}{slash} ({})\n",
+                    node.name, node.size
+                ));
+
+                let indent = if last { "    " } else { "│   " };
+                visit(fs, child, &format!("{prefix}{indent}"), out);
+            }
+        }
+
+        let mut out = format!("/ ({})\n", self.size(ROOT));
+        visit(self, ROOT, "", &mut out);
+
+        out
+    }
+
+    /// Every directory with its human-readable size, largest first, like `du -h | sort -rh`.
+    fn du(&self) -> String {
+        let mut dirs = self.find(|id| self.is_dir(id)).collect::<Vec<_>>();
+        dirs.sort_by_key(|&id| (std::cmp::Reverse(self.size(id)), id));
 
-        let dir_size = file_size + subdir_size;
-        if dir_size >= needed_size {
-            (dir_size, cmp::min(subdir_best, dir_size))
-        } else {
-            (dir_size, subdir_best)
+        dirs.into_iter()
+            .map(|id| format!("{}\t{}\n", human_size(self.size(id)), self.path(id)))
+            .collect()
+    }
+
+    fn json(&self, id: NodeId) -> String {
+        let node = &self.nodes[id];
+        let name = json_escape(if id == ROOT { "/" } else { node.name });
+
+        match node.kind {
+            Kind::File => format!(r#"{{"name":"{name}","type":"file","size":{}}}"#, node.size),
+            Kind::Dir(_) => format!(
+                r#"{{"name":"{name}","type":"dir","size":{},"children":[{}]}}"#,
+                node.size,
+                self.children(id)
+                    .map(|c| self.json(c))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
         }
     }
+}
 
-    let (_, best) = find_min(needed_size, &root);
+pub fn part_two(input: &str) -> Option<u64> {
+    let fs = parse_input(input);
+    let candidates = Disk::default().deletion_candidates(&fs);
 
-    Some(best)
+    candidates.first().map(|&id| fs.size(id))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 7);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let default = Disk::default();
+    let disk = Disk {
+        capacity: args
+            .opt_value_from_str("--capacity")
+            .unwrap()
+            .unwrap_or(default.capacity),
+        required: args
+            .opt_value_from_str("--required")
+            .unwrap()
+            .unwrap_or(default.required),
+    };
+    let fs = parse_input(input);
+
+    if args.contains("--tree") {
+        print!("{}", fs.tree());
+    }
+    if args.contains("--du") {
+        print!("{}", fs.du());
+    }
+    if args.contains("--json") {
+        println!("{}", fs.json(ROOT));
+    }
+    if args.contains("--candidates") {
+        for id in disk.deletion_candidates(&fs) {
+            println!("{}\t{}", fs.size(id), fs.path(id));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -138,4 +358,119 @@ mod tests {
         let input = advent_of_code::read_file("examples", 7);
         assert_eq!(part_two(&input), Some(24933642));
     }
+
+    #[test]
+    fn test_reports() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = parse_input(&input);
+
+        assert_eq!(
+            fs.tree(),
+            "\
+/ (48381165)
+├── a/ (94853)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d/ (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+"
+        );
+        assert_eq!(fs.du(), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+        assert!(fs.json(ROOT).starts_with(
+            r#"{"name":"/","type":"dir","size":48381165,"children":[{"name":"a","type":"dir","size":94853,"children":[{"name":"e","#
+        ));
+    }
+
+    #[test]
+    fn test_deletion_candidates() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = parse_input(&input);
+
+        let paths = |disk: Disk| {
+            disk.deletion_candidates(&fs)
+                .into_iter()
+                .map(|id| fs.path(id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(Disk::default()), ["/d", "/"]);
+        assert_eq!(
+            paths(Disk {
+                capacity: 48_400_000,
+                required: 100_000,
+            }),
+            ["/a", "/d", "/"]
+        );
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+        assert_eq!(human_size(20 * 1024 * 1024 + 1), "21M");
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = parse_input(&input);
+
+        let e = fs.find(|id| fs.nodes[id].name == "e").next().unwrap();
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(e), 584);
+        assert_eq!(fs.size(ROOT), 48381165);
+
+        let big_files = fs
+            .find(|id| !fs.is_dir(id) && fs.size(id) > 8_000_000)
+            .map(|id| fs.path(id))
+            .collect::<Vec<_>>();
+        assert_eq!(big_files, vec!["/b.txt", "/c.dat", "/d/d.log"]);
+    }
+
+    #[test]
+    fn test_unusual_sessions() {
+        let fs = parse_input(
+            "\
+$ cd /a/b
+$ ls
+10000000000 huge
+$ ls
+10000000000 huge
+$ cd ../../c
+$ ls
+5 x
+$ cd /a
+$ ls
+dir b",
+        );
+
+        assert_eq!(fs.size(ROOT), 10000000005);
+        assert_eq!(fs.size(fs.child(ROOT, "a").unwrap()), 10000000000);
+        assert_eq!(
+            fs.find(|id| fs.is_dir(id))
+                .map(|d| fs.path(d))
+                .collect::<Vec<_>>(),
+            ["/", "/a", "/a/b", "/c"]
+        );
+    }
+
+    #[test]
+    fn test_bad_session() {
+        let mut fs = FileSystem::new();
+        let mut cwd = ROOT;
+        let mut run = |line| parse_line(&mut fs, &mut cwd, &mut Cursor::new(line));
+
+        assert!(run("12 f").is_ok());
+        assert!(run("dir f").is_err());
+        assert!(run("$ cd f").is_err());
+        assert!(run("$ rm f").is_err());
+    }
 }
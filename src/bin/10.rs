@@ -1,93 +1,330 @@
+use std::iter;
+
+use advent_of_code::helpers::{ocr, parse};
 use itertools::Itertools;
 
-enum Instr {
-    Noop,
-    Addx(i32),
+/// An instruction the CPU understands: how many arguments it takes, how many cycles it runs
+/// for, and what it does to the `X` register once it completes.
+#[derive(Clone, Copy)]
+struct Op {
+    name: &'static str,
+    n_args: usize,
+    cycles: u32,
+    effect: fn(x: &mut i32, args: &[i32]),
 }
 
-use Instr::*;
+const ISA: &[Op] = &[
+    Op {
+        name: "noop",
+        n_args: 0,
+        cycles: 1,
+        effect: |_, _| {},
+    },
+    Op {
+        name: "addx",
+        n_args: 1,
+        cycles: 2,
+        effect: |x, args| *x += args[0],
+    },
+];
 
-fn parse_input(input: &str) -> Vec<Instr> {
-    input
-        .lines()
-        .map(|l| match l {
-            "noop" => Noop,
-            l => {
-                let (_, n) = l.split_whitespace().collect_tuple().unwrap();
-                Addx(n.parse().unwrap())
-            }
-        })
-        .collect_vec()
+struct Instr<'a> {
+    op: &'a Op,
+    args: Vec<i32>,
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let instrs = parse_input(input);
+/// What happened in one clock cycle. Cycles are counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tick {
+    cycle: u32,
+    /// `X` while the cycle runs.
+    during: i32,
+    /// `X` once the cycle is over.
+    after: i32,
+}
 
-    let mut cycle = 1;
-    let mut reg = vec![1, 1];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Breakpoint {
+    Cycle(u32),
+    /// Stops in any cycle during which `X` has this value.
+    X(i32),
+}
 
-    for instr in instrs {
-        match instr {
-            Noop => {
-                cycle += 1;
-                reg.push(*reg.last().unwrap())
-            }
-            Addx(n) => {
-                cycle += 1;
-                reg.push(*reg.last().unwrap());
-                cycle += 1;
-                reg.push(*reg.last().unwrap() + n)
-            }
+impl Breakpoint {
+    fn hit(&self, tick: &Tick) -> bool {
+        match *self {
+            Breakpoint::Cycle(c) => tick.cycle == c,
+            Breakpoint::X(x) => tick.during == x,
         }
     }
+}
+
+struct Cpu<'a> {
+    program: Vec<Instr<'a>>,
+    pc: usize,
+    /// Cycles already spent on the instruction at `pc`.
+    busy: u32,
+    cycle: u32,
+    x: i32,
+    breakpoints: Vec<Breakpoint>,
+}
+
+fn parse_program<'a>(input: &str, isa: &'a [Op]) -> parse::Result<Vec<Instr<'a>>> {
+    parse::each_line(input, |l| {
+        let at = *l;
+        let name = l.word()?;
+        let op = isa
+            .iter()
+            .find(|op| op.name == name)
+            .ok_or_else(|| at.error(format!("unknown instruction {name:?}")))?;
 
-    let interesting = [20, 60, 100, 140, 180, 220];
-    let ans = interesting.into_iter().map(|i| (i as i32) * reg[i]).sum();
+        let args = (0..op.n_args)
+            .map(|_| {
+                l.tag(" ")?;
+                l.int()
+            })
+            .collect::<parse::Result<_>>()?;
 
-    Some(ans)
+        Ok(Instr { op, args })
+    })
 }
 
-pub fn part_two(input: &str) -> Option<i32> {
-    let instrs = parse_input(input);
+impl<'a> Cpu<'a> {
+    fn new(program: Vec<Instr<'a>>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            busy: 0,
+            cycle: 0,
+            x: 1,
+            breakpoints: vec![],
+        }
+    }
+
+    fn add_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.push(bp);
+    }
 
-    let mut cycle = 1;
-    let mut reg = vec![1, 1];
+    /// Runs a single cycle, or returns `None` once the program is over.
+    fn step(&mut self) -> Option<Tick> {
+        let instr = self.program.get(self.pc)?;
+        let during = self.x;
 
-    for instr in instrs {
-        match instr {
-            Noop => {
-                cycle += 1;
-                reg.push(*reg.last().unwrap())
-            }
-            Addx(n) => {
-                cycle += 1;
-                reg.push(*reg.last().unwrap());
-                cycle += 1;
-                reg.push(*reg.last().unwrap() + n)
+        self.cycle += 1;
+        self.busy += 1;
+        if self.busy == instr.op.cycles {
+            (instr.op.effect)(&mut self.x, &instr.args);
+            self.pc += 1;
+            self.busy = 0;
+        }
+
+        Some(Tick {
+            cycle: self.cycle,
+            during,
+            after: self.x,
+        })
+    }
+
+    /// Runs until a cycle hits one of the breakpoints, and returns that cycle.
+    fn resume(&mut self) -> Option<Tick> {
+        while let Some(t) = self.step() {
+            if self.breakpoints.iter().any(|bp| bp.hit(&t)) {
+                return Some(t);
             }
         }
+
+        None
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Tick> {
+        self.step()
+    }
+}
+
+fn parse_input(input: &str) -> Cpu<'static> {
+    Cpu::new(parse_program(input, ISA).unwrap())
+}
+
+pub fn part_one(input: &str) -> Option<i32> {
+    let mut cpu = parse_input(input);
+    for cycle in [20, 60, 100, 140, 180, 220] {
+        cpu.add_breakpoint(Breakpoint::Cycle(cycle));
+    }
+
+    Some(
+        iter::from_fn(|| cpu.resume())
+            .map(|t| t.cycle as i32 * t.during)
+            .sum(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Glyphs {
+    /// `#` for lit pixels and `.` for dark ones.
+    Ascii,
+    /// A full block per lit pixel.
+    Blocks,
+    /// Half blocks, so that each character covers two rows.
+    HalfBlocks,
+}
+
+/// A screen that draws one pixel per clock cycle, row by row.
+struct Crt {
+    width: usize,
+    height: usize,
+    /// How many pixels of the sprite centred on `X` are lit.
+    sprite_width: usize,
+    pixels: Vec<Vec<bool>>,
+}
+
+impl Crt {
+    fn new(width: usize, height: usize, sprite_width: usize) -> Self {
+        Self {
+            width,
+            height,
+            sprite_width,
+            pixels: vec![vec![false; width]; height],
+        }
     }
 
-    for i in 1..=240 {
-        let pix = ((i as i32) - 1) % 40;
-        if pix.abs_diff(reg[i] % 40) <= 1 {
-            print!("#");
-        } else {
-            print!(" ");
+    /// Draws the pixel of `tick`'s cycle, if it falls on the screen.
+    fn draw(&mut self, tick: &Tick) {
+        let pos = (tick.cycle - 1) as usize;
+        let (row, col) = (pos / self.width, pos % self.width);
+        if row >= self.height {
+            return;
         }
 
-        if i % 40 == 0 {
-            println!();
+        let start = tick.during as i64 - (self.sprite_width as i64 - 1) / 2;
+        let sprite = start..start + self.sprite_width as i64;
+        self.pixels[row][col] = sprite.contains(&(col as i64));
+    }
+
+    fn render(&self, glyphs: Glyphs) -> String {
+        let line = |row: &[bool], lit: char, dark: char| {
+            row.iter()
+                .map(|&p| if p { lit } else { dark })
+                .collect::<String>()
+        };
+
+        match glyphs {
+            Glyphs::Ascii => self.pixels.iter().map(|r| line(r, '#', '.')).join("\n"),
+            Glyphs::Blocks => self.pixels.iter().map(|r| line(r, '█', ' ')).join("\n"),
+            Glyphs::HalfBlocks => self
+                .pixels
+                .chunks(2)
+                .map(|rows| {
+                    (0..self.width)
+                        .map(|c| match (rows[0][c], rows.get(1).is_some_and(|r| r[c])) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        })
+                        .collect::<String>()
+                })
+                .join("\n"),
         }
     }
 
-    None
+    /// The image as a plain PBM file, where `1` is a lit pixel.
+    fn to_pbm(&self) -> String {
+        let rows = self
+            .pixels
+            .iter()
+            .map(|r| r.iter().map(|&p| if p { '1' } else { '0' }).join(" "))
+            .join("\n");
+
+        format!("P1\n{} {}\n{rows}\n", self.width, self.height)
+    }
+
+    /// The image as a greyscale PNG file, with lit pixels white.
+    fn to_png(&self) -> Vec<u8> {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data = self
+            .pixels
+            .iter()
+            .flatten()
+            .map(|&p| if p { 0xff } else { 0 })
+            .collect_vec();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+
+        png
+    }
+}
+
+fn run_crt(input: &str, mut crt: Crt) -> Crt {
+    let n_pixels = crt.width * crt.height;
+    for t in parse_input(input).take(n_pixels) {
+        crt.draw(&t);
+    }
+
+    crt
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    let crt = run_crt(input, Crt::new(40, 6, 3));
+
+    // fall back to the raw image so unknown glyphs can still be read by eye.
+    Some(ocr::recognize(&crt.pixels).unwrap_or_else(|_| format!("\n{}", crt.render(Glyphs::Ascii))))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let print = |t: Tick| {
+        println!(
+            "cycle {:>3}: X = {:>3} -> {:>3}",
+            t.cycle, t.during, t.after
+        )
+    };
+
+    if args.contains("--trace") {
+        parse_input(input).for_each(print);
+    }
+
+    let mut cpu = parse_input(input);
+    if let Some(c) = args.opt_value_from_str("--break-cycle").unwrap() {
+        cpu.add_breakpoint(Breakpoint::Cycle(c));
+    }
+    if let Some(x) = args.opt_value_from_str("--break-x").unwrap() {
+        cpu.add_breakpoint(Breakpoint::X(x));
+    }
+    if !cpu.breakpoints.is_empty() {
+        iter::from_fn(|| cpu.resume()).for_each(print);
+    }
+
+    let crt = run_crt(input, Crt::new(40, 6, 3));
+    let glyphs = args
+        .opt_value_from_fn("--show", |s| match s {
+            "ascii" => Ok(Glyphs::Ascii),
+            "blocks" => Ok(Glyphs::Blocks),
+            "half" => Ok(Glyphs::HalfBlocks),
+            _ => Err("expected ascii, blocks or half"),
+        })
+        .unwrap();
+    if let Some(glyphs) = glyphs {
+        println!("{}", crt.render(glyphs));
+    }
+    if let Some(path) = args.opt_value_from_str::<_, String>("--pbm").unwrap() {
+        std::fs::write(path, crt.to_pbm()).unwrap();
+    }
+    if let Some(path) = args.opt_value_from_str::<_, String>("--png").unwrap() {
+        std::fs::write(path, crt.to_png()).unwrap();
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +340,91 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 10);
-        assert_eq!(part_two(&input), None);
+        let expected = "
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+        assert_eq!(part_two(&input), Some(expected.to_owned()));
+    }
+
+    #[test]
+    fn test_crt() {
+        let input = advent_of_code::read_file("examples", 10);
+        let crt = run_crt(&input, Crt::new(40, 6, 3));
+
+        assert_eq!(
+            crt.render(Glyphs::Blocks).lines().next(),
+            Some("██  ██  ██  ██  ██  ██  ██  ██  ██  ██  ")
+        );
+        assert_eq!(
+            crt.render(Glyphs::HalfBlocks).lines().next(),
+            Some("██▄ ▀▀▄▄█▀  ██▄ ▀▀▄▄█▀  ██▄ ▀▀▄▄█▀  ██▄ ")
+        );
+
+        let pbm = crt.to_pbm();
+        assert!(pbm.starts_with("P1\n40 6\n1 1 0 0 1 1 0 0"));
+        assert_eq!(pbm.lines().count(), 8);
+
+        let png = crt.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_crt_geometry() {
+        let input = advent_of_code::read_file("examples", 10);
+        let crt = run_crt(&input, Crt::new(20, 3, 1));
+        let expected = "\
+.#...#..#...#....#..
+....................
+..#.....#....#.....#";
+
+        assert_eq!(crt.render(Glyphs::Ascii), expected);
+    }
+
+    #[test]
+    fn test_ticks() {
+        let ticks = parse_input("noop\naddx 3\naddx -5").collect_vec();
+        let x = ticks.iter().map(|t| (t.during, t.after)).collect_vec();
+
+        assert_eq!(x, [(1, 1), (1, 1), (1, 4), (4, 4), (4, -1)]);
+        assert_eq!(ticks.last().unwrap().cycle, 5);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut cpu = parse_input("noop\naddx 3\naddx -5\nnoop");
+        cpu.add_breakpoint(Breakpoint::X(4));
+        cpu.add_breakpoint(Breakpoint::Cycle(6));
+
+        assert_eq!(cpu.resume().map(|t| t.cycle), Some(4));
+        assert_eq!(cpu.resume().map(|t| t.cycle), Some(5));
+        assert_eq!(cpu.resume().map(|t| t.cycle), Some(6));
+        assert_eq!(cpu.resume(), None);
+    }
+
+    #[test]
+    fn test_custom_instruction() {
+        const ISA_WITH_MUL: &[Op] = &[
+            ISA[0],
+            ISA[1],
+            Op {
+                name: "mulx",
+                n_args: 1,
+                cycles: 3,
+                effect: |x, args| *x *= args[0],
+            },
+        ];
+
+        let program = parse_program("addx 2\nmulx 5\nnoop", ISA_WITH_MUL).unwrap();
+        let ticks = Cpu::new(program).collect_vec();
+
+        assert_eq!(ticks.len(), 6);
+        assert_eq!(ticks[4].during, 3);
+        assert_eq!(ticks[4].after, 15);
+        assert!(parse_program("mulx 5", ISA).is_err());
     }
 }
@@ -1,268 +1,584 @@
-#![feature(drain_filter)]
-
-#[macro_use]
-extern crate scan_fmt;
-
-use std::*;
-
+use std::{
+    collections::{HashMap, VecDeque},
+    *,
+};
+
+use advent_of_code::helpers::{
+    math,
+    parse::{self, Cursor},
+};
 use itertools::Itertools;
 
-#[derive(Debug)]
-enum Token {
-    Old,
-    Const(u64),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
 }
 
-use Token::*;
-
-impl Token {
-    fn eval(&self, old: u64) -> u64 {
+impl Operator {
+    /// `None` on overflow, or when dividing by a zero that was computed rather than written.
+    fn apply(self, a: u64, b: u64) -> Option<u64> {
         match self {
-            Old => old,
-            Const(n) => *n,
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => Some(a.saturating_sub(b)),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Div => a.checked_div(b),
         }
     }
-}
 
-impl str::FromStr for Token {
-    type Err = num::ParseIntError;
+    /// `a op b` modulo `m`, for operands already reduced modulo `m`.
+    fn apply_mod(self, a: u64, b: u64, m: u64) -> u64 {
+        let (a, b, m) = (a as u128, b as u128, m as u128);
+        let n = match self {
+            Operator::Add => a + b,
+            Operator::Mul => a * b,
+            Operator::Sub | Operator::Div => unreachable!("only + and * commute with modulo"),
+        };
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "old" {
-            Ok(Old)
-        } else {
-            let n = s.parse::<u64>()?;
-            Ok(Const(n))
-        }
+        (n % m) as u64
     }
 }
 
-#[derive(Debug)]
-enum Operator {
-    Add,
-    Mul,
+/// The right-hand side of `new = ...`.
+#[derive(Debug, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Const(u64),
+    Binary(Box<Expr>, Operator, Box<Expr>),
 }
 
-use Operator::*;
+impl Expr {
+    /// Worry never goes below zero, so subtraction saturates. `None` if it overflows or is
+    /// divided by zero.
+    fn eval(&self, old: u64) -> Option<u64> {
+        match self {
+            Expr::Old => Some(old),
+            Expr::Const(n) => Some(*n),
+            Expr::Binary(a, op, b) => op.apply(a.eval(old)?, b.eval(old)?),
+        }
+    }
+
+    /// The result modulo `m`. Only valid when [`Expr::commutes_with_modulo`] holds.
+    fn eval_mod(&self, old: u64, m: u64) -> u64 {
+        match self.eval(old) {
+            Some(n) => n % m,
+            None => self.eval_reduced(old, m),
+        }
+    }
 
-impl str::FromStr for Operator {
-    type Err = String;
+    /// Like [`Expr::eval_mod`], but reducing after every operation so that nothing overflows.
+    fn eval_reduced(&self, old: u64, m: u64) -> u64 {
+        match self {
+            Expr::Old => old % m,
+            Expr::Const(n) => n % m,
+            Expr::Binary(a, op, b) => {
+                op.apply_mod(a.eval_reduced(old, m), b.eval_reduced(old, m), m)
+            }
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "+" => Ok(Add),
-            "*" => Ok(Mul),
-            _ => Err(format!("bad operator {s}").to_owned()),
+    /// Whether the result modulo any `m` only depends on `old` modulo `m`.
+    fn commutes_with_modulo(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => true,
+            Expr::Binary(a, op, b) => {
+                matches!(op, Operator::Add | Operator::Mul)
+                    && a.commutes_with_modulo()
+                    && b.commutes_with_modulo()
+            }
         }
     }
 }
 
-#[derive(Debug)]
-struct Operation {
-    a: Token,
-    b: Token,
-    op: Operator,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Predicate {
+    DivisibleBy(u64),
+    LessThan(u64),
+    GreaterThan(u64),
+    EqualTo(u64),
 }
 
-impl Operation {
-    fn apply(&self, old: u64) -> u64 {
-        let a = self.a.eval(old);
-        let b = self.b.eval(old);
-
-        match self.op {
-            Add => a + b,
-            Mul => a * b,
+impl Predicate {
+    fn holds(&self, n: u64) -> bool {
+        match *self {
+            Predicate::DivisibleBy(d) => n.is_multiple_of(d),
+            Predicate::LessThan(m) => n < m,
+            Predicate::GreaterThan(m) => n > m,
+            Predicate::EqualTo(m) => n == m,
         }
     }
 }
 
+/// Where a monkey throws an item: to the target of the first rule that holds, or else to
+/// `otherwise`.
 #[derive(Debug)]
 struct Test {
-    div_by: u64,
-    if_true: usize,
-    if_false: usize,
+    rules: Vec<(Predicate, usize)>,
+    otherwise: usize,
 }
 
 impl Test {
     fn check(&self, n: u64) -> usize {
-        if n % self.div_by == 0 {
-            self.if_true
-        } else {
-            self.if_false
-        }
+        self.rules
+            .iter()
+            .find(|(p, _)| p.holds(n))
+            .map_or(self.otherwise, |&(_, to)| to)
+    }
+
+    fn targets(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rules.iter().map(|&(_, to)| to).chain([self.otherwise])
+    }
+
+    /// The divisors the rules test for.
+    fn divisors(&self) -> impl Iterator<Item = u64> + '_ {
+        self.rules.iter().filter_map(|(p, _)| match p {
+            Predicate::DivisibleBy(d) => Some(*d),
+            _ => None,
+        })
     }
 }
 
 #[derive(Debug)]
 struct Monkey {
     n: usize,
-    items: Vec<u64>,
-    operation: Operation,
+    items: VecDeque<u64>,
+    operation: Expr,
     test: Test,
     n_inspected: u64,
 }
 
-fn parse_input(input: &str) -> Vec<Monkey> {
-    fn parse_monkey(lines: &mut str::Lines) -> Option<Monkey> {
-        let n = scan_fmt!(lines.next()?, "Monkey {d}:", usize).unwrap();
-        let items = {
-            let items = lines.next()?.strip_prefix("  Starting items: ").unwrap();
-            items
-                .split(", ")
-                .map(|x| x.parse::<u64>().unwrap())
-                .collect_vec()
-        };
+fn spaces(c: &mut Cursor) {
+    while c.eat(" ") {}
+}
 
-        let operation = {
-            let (a, op, b) = scan_fmt!(
-                lines.next()?,
-                "  Operation: new = {} {} {}",
-                String,
-                String,
-                String
-            )
-            .unwrap();
-
-            Operation {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
-                op: op.parse().unwrap(),
-            }
+fn parse_factor(c: &mut Cursor) -> parse::Result<Expr> {
+    spaces(c);
+
+    if c.eat("(") {
+        let e = parse_expr(c)?;
+        spaces(c);
+        c.tag(")")?;
+        Ok(e)
+    } else if c.eat("old") {
+        Ok(Expr::Old)
+    } else if c.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+        Ok(Expr::Const(c.int()?))
+    } else {
+        let found = c.rest().split_whitespace().next().unwrap_or("end of line");
+        Err(c.error(format!("expected `old`, a number or `(`, found {found:?}")))
+    }
+}
+
+/// Parses operands separated by any of `ops`, grouping from the left.
+fn parse_chain(
+    c: &mut Cursor,
+    ops: &[(&str, Operator)],
+    operand: fn(&mut Cursor) -> parse::Result<Expr>,
+) -> parse::Result<Expr> {
+    let mut lhs = operand(c)?;
+
+    loop {
+        spaces(c);
+        let Some(&(_, op)) = ops.iter().find(|(sym, _)| c.eat(sym)) else {
+            return Ok(lhs);
         };
+        let rhs = operand(c)?;
+        if op == Operator::Div && rhs == Expr::Const(0) {
+            return Err(c.error("cannot divide by 0"));
+        }
+
+        lhs = Expr::Binary(Box::new(lhs), op, Box::new(rhs));
+    }
+}
+
+fn parse_term(c: &mut Cursor) -> parse::Result<Expr> {
+    parse_chain(
+        c,
+        &[("*", Operator::Mul), ("/", Operator::Div)],
+        parse_factor,
+    )
+}
 
-        let test = {
-            let div_by = scan_fmt!(lines.next()?, "  Test: divisible by {d}", u64).unwrap();
+fn parse_expr(c: &mut Cursor) -> parse::Result<Expr> {
+    parse_chain(c, &[("+", Operator::Add), ("-", Operator::Sub)], parse_term)
+}
 
-            let if_true =
-                scan_fmt!(lines.next()?, "    If true: throw to monkey {d}", usize).unwrap();
+fn parse_predicate(c: &mut Cursor) -> parse::Result<Predicate> {
+    let predicate: fn(u64) -> Predicate = if c.eat("divisible by ") {
+        Predicate::DivisibleBy
+    } else if c.eat("less than ") {
+        Predicate::LessThan
+    } else if c.eat("greater than ") {
+        Predicate::GreaterThan
+    } else if c.eat("equal to ") {
+        Predicate::EqualTo
+    } else {
+        return Err(c.error("expected `divisible by`, `less than`, `greater than` or `equal to`"));
+    };
 
-            let if_false =
-                scan_fmt!(lines.next()?, "    If false: throw to monkey {d}", usize).unwrap();
+    match predicate(c.int()?) {
+        Predicate::DivisibleBy(0) => Err(c.error("cannot test for divisibility by 0")),
+        p => Ok(p),
+    }
+}
 
-            let _ = lines.next();
+/// The next line of `block`, with its indentation skipped.
+fn next_line<'a>(block: &mut Cursor<'a>) -> parse::Result<Cursor<'a>> {
+    let mut l = block
+        .line()
+        .ok_or_else(|| block.error("unexpected end of monkey"))?;
+    l.skip_ws();
 
-            Test {
-                div_by,
-                if_true,
-                if_false,
-            }
-        };
+    Ok(l)
+}
 
-        Some(Monkey {
-            n,
-            items,
-            operation,
-            test,
-            n_inspected: 0,
-        })
+/// A line of the form `<tag>: throw to monkey <n>`.
+fn parse_throw(l: &mut Cursor) -> parse::Result<usize> {
+    l.tag(": throw to monkey ")?;
+    let to = l.int()?;
+    l.end()?;
+
+    Ok(to)
+}
+
+/// Either the puzzle's `divisible by` test with its `If true` and `If false` lines, or a list of
+/// `If <predicate>` rules that ends with an `Otherwise` line.
+fn parse_test(b: &mut Cursor) -> parse::Result<Test> {
+    let mut l = next_line(b)?;
+    l.tag("Test:")?;
+    spaces(&mut l);
+
+    if !l.is_empty() {
+        let predicate = parse_predicate(&mut l)?;
+        l.end()?;
+
+        let mut l = next_line(b)?;
+        l.tag("If true")?;
+        let if_true = parse_throw(&mut l)?;
+
+        let mut l = next_line(b)?;
+        l.tag("If false")?;
+        let otherwise = parse_throw(&mut l)?;
+
+        return Ok(Test {
+            rules: vec![(predicate, if_true)],
+            otherwise,
+        });
     }
 
-    let mut lines = input.lines();
-    let mut monkeys = vec![];
+    let mut rules = vec![];
+    loop {
+        let mut l = next_line(b)?;
+        if l.eat("Otherwise") {
+            let otherwise = parse_throw(&mut l)?;
+            return Ok(Test { rules, otherwise });
+        }
 
-    while let Some(monkey) = parse_monkey(&mut lines) {
-        monkeys.push(monkey);
+        l.tag("If ")?;
+        let predicate = parse_predicate(&mut l)?;
+        rules.push((predicate, parse_throw(&mut l)?));
     }
+}
 
-    monkeys
+fn parse_monkey(b: &mut Cursor) -> parse::Result<Monkey> {
+    let mut l = next_line(b)?;
+    l.tag("Monkey ")?;
+    let n = l.int()?;
+    l.tag(":")?;
+    l.end()?;
+
+    let mut l = next_line(b)?;
+    l.tag("Starting items:")?;
+    spaces(&mut l);
+    let items = if l.is_empty() {
+        VecDeque::new()
+    } else {
+        l.list(", ", Cursor::int)?.into()
+    };
+    l.end()?;
+
+    let mut l = next_line(b)?;
+    l.tag("Operation: new =")?;
+    let operation = parse_expr(&mut l)?;
+    l.end()?;
+
+    let test = parse_test(b)?;
+
+    Ok(Monkey {
+        n,
+        items,
+        operation,
+        test,
+        n_inspected: 0,
+    })
 }
 
-fn borrow_all<T>(v: &mut Vec<T>) -> Vec<*mut T> {
-    let v_raw = v.as_mut_ptr();
-    let mut borrowed = vec![];
+/// Parses the monkeys and checks that they are numbered in order and only throw to each other.
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, String> {
+    let monkeys = parse::each_block(input, parse_monkey).map_err(|e| e.to_string())?;
 
-    unsafe {
-        for i in 0..v.len() {
-            borrowed.push(v_raw.add(i));
+    for (i, m) in monkeys.iter().enumerate() {
+        if m.n != i {
+            return Err(format!("monkey {} is listed as monkey {i}", m.n));
+        }
+
+        if let Some(to) = m.test.targets().find(|&to| to == i || to >= monkeys.len()) {
+            return Err(format!("monkey {i} cannot throw to monkey {to}"));
         }
     }
 
-    borrowed
+    Ok(monkeys)
 }
 
-pub fn part_one(input: &str) -> Option<u64> {
-    fn one_round(monkeys: &Vec<*mut Monkey>) {
-        unsafe {
-            for i in 0..monkeys.len() {
-                let m = monkeys[i];
-                while !(*m).items.is_empty() {
-                    let mut item = (*m).items.remove(0);
-                    item = (*m).operation.apply(item);
-                    (*m).n_inspected += 1;
-
-                    item /= 3;
-
-                    let throw_to = (*m).test.check(item);
-                    let throw_to = monkeys[throw_to];
-                    (*throw_to).items.push(item);
-                }
-            }
+fn parse_input(input: &str) -> Vec<Monkey> {
+    parse_monkeys(input).unwrap()
+}
+
+/// How worry levels are kept in check after each inspection.
+enum Relief {
+    /// Worry is divided by this much once an item turns out undamaged.
+    Divide(u64),
+    /// Worry is reduced modulo this, which must be a multiple of every monkey's divisor.
+    Modulo(u64),
+}
+
+impl Relief {
+    /// The worry level after `operation` and relief, or `None` if it overflows.
+    fn inspect(&self, operation: &Expr, worry: u64) -> Option<u64> {
+        match *self {
+            Relief::Divide(n) => Some(operation.eval(worry)? / n),
+            Relief::Modulo(n) => Some(operation.eval_mod(worry, n)),
+        }
+    }
+}
+
+fn round(monkeys: &mut [Monkey], relief: &Relief) -> Result<(), String> {
+    for i in 0..monkeys.len() {
+        while let Some(item) = monkeys[i].items.pop_front() {
+            let m = &mut monkeys[i];
+            m.n_inspected += 1;
+
+            let worry = relief.inspect(&m.operation, item).ok_or_else(|| {
+                format!("monkey {i}: worry {item} overflows or is divided by zero")
+            })?;
+            let throw_to = m.test.check(worry);
+            monkeys[throw_to].items.push_back(worry);
         }
     }
 
-    let mut monkeys = parse_input(input);
-    let monkeys = borrow_all(&mut monkeys);
+    Ok(())
+}
 
-    for _ in 0..20 {
-        one_round(&monkeys);
+/// The product of the two largest inspection counts after `rounds` rounds.
+fn monkey_business(mut monkeys: Vec<Monkey>, rounds: usize, relief: Relief) -> Result<u64, String> {
+    for _ in 0..rounds {
+        round(&mut monkeys, &relief)?;
     }
 
-    let n_inspected_desc = unsafe {
-        monkeys
-            .into_iter()
-            .map(|m| (*m).n_inspected)
-            .sorted()
-            .rev()
-            .collect_vec()
-    };
+    Ok(monkeys
+        .iter()
+        .map(|m| m.n_inspected)
+        .sorted()
+        .rev()
+        .take(2)
+        .product())
+}
 
-    Some(n_inspected_desc[0] * n_inspected_desc[1])
+/// The rounds one item goes through once worry is kept modulo the lcm of all divisors.
+///
+/// Items never affect each other, and an item's whole future only depends on which monkey holds
+/// it and its worry at the start of a round, so its rounds repeat from `cycle_start` on.
+struct Trajectory {
+    /// The monkeys that inspect the item in each round, until the first repeated round.
+    rounds: Vec<Vec<usize>>,
+    cycle_start: usize,
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    fn one_round(monkeys: &Vec<*mut Monkey>) {
-        unsafe {
-            let mod_by: u64 = monkeys.iter().map(|&m| (*m).test.div_by).product();
-
-            for i in 0..monkeys.len() {
-                let m = monkeys[i];
-                while !(*m).items.is_empty() {
-                    let mut item = (*m).items.remove(0);
-                    item %= mod_by;
-                    item = (*m).operation.apply(item);
-                    (*m).n_inspected += 1;
-
-                    let throw_to = (*m).test.check(item);
-                    let throw_to = monkeys[throw_to];
-                    (*throw_to).items.push(item);
+impl Trajectory {
+    fn trace(monkeys: &[Monkey], lcm: u64, mut monkey: usize, mut worry: u64) -> Self {
+        let mut seen = HashMap::new();
+        let mut rounds = vec![];
+
+        while let collections::hash_map::Entry::Vacant(e) = seen.entry((monkey, worry)) {
+            e.insert(rounds.len());
+
+            // the item keeps going within a round as long as it is thrown to a later monkey.
+            let mut inspected_by = vec![];
+            loop {
+                let m = &monkeys[monkey];
+                inspected_by.push(monkey);
+                worry = m.operation.eval_mod(worry, lcm);
+
+                let throw_to = m.test.check(worry);
+                let done = throw_to <= monkey;
+                monkey = throw_to;
+                if done {
+                    break;
                 }
             }
+
+            rounds.push(inspected_by);
+        }
+
+        Self {
+            rounds,
+            cycle_start: seen[&(monkey, worry)],
         }
     }
 
-    let mut monkeys = parse_input(input);
-    let monkeys = borrow_all(&mut monkeys);
+    fn cycle_len(&self) -> usize {
+        self.rounds.len() - self.cycle_start
+    }
+
+    /// The monkeys that inspect the item in round `r`, counted from 0.
+    fn round(&self, r: u64) -> &[usize] {
+        let start = self.cycle_start as u64;
+        let i = match r.checked_sub(start) {
+            None => r,
+            Some(into_cycle) => start + into_cycle % self.cycle_len() as u64,
+        };
 
-    for _ in 0..10000 {
-        one_round(&monkeys);
+        &self.rounds[i as usize]
     }
 
-    let n_inspected_desc = unsafe {
-        monkeys
-            .into_iter()
-            .map(|m| (*m).n_inspected)
-            .sorted()
-            .rev()
-            .collect_vec()
-    };
+    /// How many times each monkey inspects the item over the first `n_rounds` rounds.
+    fn inspections(&self, n_monkeys: usize, n_rounds: u64) -> Vec<u64> {
+        let mut counts = vec![0; n_monkeys];
+        let mut add = |rounds: &[Vec<usize>], times: u64| {
+            for &m in rounds.iter().flatten() {
+                counts[m] += times;
+            }
+        };
+
+        let start = self.cycle_start as u64;
+        if n_rounds <= start {
+            add(&self.rounds[..n_rounds as usize], 1);
+            return counts;
+        }
+
+        let len = self.cycle_len() as u64;
+        let (cycles, rest) = ((n_rounds - start) / len, (n_rounds - start) % len);
+        let cycle = &self.rounds[self.cycle_start..];
 
-    Some(n_inspected_desc[0] * n_inspected_desc[1])
+        add(&self.rounds[..self.cycle_start], 1);
+        add(cycle, cycles);
+        add(&cycle[..rest as usize], 1);
+
+        counts
+    }
+}
+
+/// The lcm of all divisors, modulo which worry can be kept without changing where items go.
+///
+/// This only holds when every operation is made of `+` and `*` and every rule tests for
+/// divisibility. Without any rules the modulus is 1, which is fine as items then always go to
+/// `otherwise`.
+fn worry_modulus(monkeys: &[Monkey]) -> Result<u64, String> {
+    for m in monkeys {
+        if !m.operation.commutes_with_modulo() {
+            return Err(format!(
+                "monkey {}: worry cannot be reduced through a subtraction or division",
+                m.n
+            ));
+        }
+
+        if m.test
+            .rules
+            .iter()
+            .any(|(p, _)| !matches!(p, Predicate::DivisibleBy(_)))
+        {
+            return Err(format!(
+                "monkey {}: worry cannot be reduced before a comparison",
+                m.n
+            ));
+        }
+    }
+
+    Ok(math::lcm_all(
+        monkeys.iter().flat_map(|m| m.test.divisors()),
+    ))
+}
+
+fn trajectories(monkeys: &[Monkey]) -> Result<Vec<Trajectory>, String> {
+    let lcm = worry_modulus(monkeys)?;
+
+    Ok(monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, m)| m.items.iter().map(move |&w| (i, w)))
+        .map(|(i, w)| Trajectory::trace(monkeys, lcm, i, w))
+        .collect())
+}
+
+/// Like part 2 for any number of rounds, without simulating them all.
+fn extrapolated_monkey_business(monkeys: &[Monkey], n_rounds: u64) -> Result<u128, String> {
+    let mut counts = vec![0; monkeys.len()];
+    for t in trajectories(monkeys)? {
+        for (c, n) in counts
+            .iter_mut()
+            .zip(t.inspections(monkeys.len(), n_rounds))
+        {
+            *c += n as u128;
+        }
+    }
+
+    Ok(counts.into_iter().sorted().rev().take(2).product())
+}
+
+/// Inspections by each monkey in each of the first `n_rounds` rounds, as CSV.
+fn inspections_per_round(monkeys: &[Monkey], n_rounds: u64) -> Result<String, String> {
+    let trajectories = trajectories(monkeys)?;
+    let header = (0..monkeys.len()).map(|m| format!("monkey {m}")).join(",");
+    let mut csv = format!("round,{header}\n");
+
+    for r in 0..n_rounds {
+        let mut counts = vec![0; monkeys.len()];
+        for &m in trajectories.iter().flat_map(|t| t.round(r)) {
+            counts[m] += 1;
+        }
+        csv.push_str(&format!("{},{}\n", r + 1, counts.iter().join(",")));
+    }
+
+    Ok(csv)
+}
+
+/// Prints why a part could not be solved.
+fn report<T>(result: Result<T, String>) -> Option<T> {
+    result.map_err(|e| eprintln!("{e}")).ok()
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    report(monkey_business(parse_input(input), 20, Relief::Divide(3)))
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let monkeys = parse_input(input);
+    let lcm = report(worry_modulus(&monkeys))?;
+
+    report(monkey_business(monkeys, 10000, Relief::Modulo(lcm)))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 11);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let monkeys = parse_input(input);
+    if let Some(n) = args.opt_value_from_str("--rounds").unwrap() {
+        match extrapolated_monkey_business(&monkeys, n) {
+            Ok(business) => println!("monkey business after {n} rounds: {business}"),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+    if let Some(n) = args.opt_value_from_str("--per-round").unwrap() {
+        match inspections_per_round(&monkeys, n) {
+            Ok(csv) => print!("{csv}"),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -280,4 +596,247 @@ mod tests {
         let input = advent_of_code::read_file("examples", 11);
         assert_eq!(part_two(&input), Some(2713310158));
     }
+
+    #[test]
+    fn test_extrapolation() {
+        let input = advent_of_code::read_file("examples", 11);
+        let monkeys = parse_input(&input);
+
+        assert_eq!(
+            extrapolated_monkey_business(&monkeys, 10000),
+            Ok(2713310158)
+        );
+        assert_eq!(
+            extrapolated_monkey_business(&monkeys, 20).unwrap() as u64,
+            monkey_business(parse_input(&input), 20, Relief::Modulo(96577)).unwrap()
+        );
+        assert!(
+            extrapolated_monkey_business(&monkeys, 1_000_000_000_000).unwrap() > u64::MAX as u128
+        );
+    }
+
+    #[test]
+    fn test_inspections_per_round() {
+        let input = advent_of_code::read_file("examples", 11);
+        let csv = inspections_per_round(&parse_input(&input), 1000).unwrap();
+        let lines = csv.lines().collect_vec();
+
+        assert_eq!(lines[0], "round,monkey 0,monkey 1,monkey 2,monkey 3");
+        assert_eq!(lines[1], "1,2,4,3,6");
+        assert_eq!(lines.len(), 1001);
+
+        let totals = lines[1..].iter().fold([0; 4], |mut acc, l| {
+            for (a, n) in acc.iter_mut().zip(l.split(',').skip(1)) {
+                *a += n.parse::<u64>().unwrap();
+            }
+            acc
+        });
+        assert_eq!(totals, [5204, 4792, 199, 5192]);
+    }
+
+    #[test]
+    fn test_expressions() {
+        let parse = |s: &str| {
+            let mut c = Cursor::new(s);
+            parse_expr(&mut c).and_then(|e| c.end().map(|_| e))
+        };
+
+        assert_eq!(parse("old * old").unwrap().eval(7), Some(49));
+        assert_eq!(parse("old + 2 * 3").unwrap().eval(1), Some(7));
+        assert_eq!(parse("(old + 2) * 3").unwrap().eval(1), Some(9));
+        assert_eq!(parse("old - 10 - 3").unwrap().eval(20), Some(7));
+        assert_eq!(parse(" ( old/2 )-(old/ 3)").unwrap().eval(12), Some(2));
+        assert_eq!(parse("3 - old").unwrap().eval(5), Some(0));
+
+        assert_eq!(parse("old * old * old").unwrap().eval(1 << 22), None);
+        assert_eq!(parse("old / (old - old)").unwrap().eval(3), None);
+
+        for (bad, col) in [
+            ("old +", 6),
+            ("(old * 2", 9),
+            ("old ^ 2", 5),
+            ("new", 1),
+            ("old / 0", 8),
+        ] {
+            let e = parse(bad).unwrap_err();
+            assert_eq!(e.col, col, "{bad:?}: {e}");
+        }
+    }
+
+    #[test]
+    fn test_cubic_operation() {
+        let input = advent_of_code::read_file("inputs", 11).replacen(
+            "new = old * 17",
+            "new = old * old * old",
+            1,
+        );
+        let monkeys = parse_input(&input);
+        let lcm = worry_modulus(&monkeys).unwrap();
+        assert_eq!(lcm, 9699690);
+
+        let cube = &monkeys[0].operation;
+        assert_eq!(cube.eval(lcm - 1), None);
+        for w in [lcm - 1, lcm / 2, 123456] {
+            assert_eq!(
+                cube.eval_mod(w, lcm) as u128,
+                (w as u128).pow(3) % lcm as u128
+            );
+        }
+
+        // checked against a simulation with arbitrary precision integers.
+        assert_eq!(part_two(&input), Some(16605543864));
+        assert_eq!(
+            extrapolated_monkey_business(&monkeys, 10000),
+            Ok(16605543864)
+        );
+        assert_eq!(
+            monkey_business(parse_input(&input), 10000, Relief::Divide(1)),
+            Err("monkey 0: worry 6588369 overflows or is divided by zero".to_owned())
+        );
+    }
+
+    const RULES: &str = "\
+Monkey 0:
+  Starting items: 10, 70, 99
+  Operation: new = (old - 1) / 2
+  Test:
+    If divisible by 7: throw to monkey 1
+    If greater than 30: throw to monkey 2
+    Otherwise: throw to monkey 3
+
+Monkey 1:
+  Starting items:
+  Operation: new = old
+  Test: equal to 0
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items:
+  Operation: new = old
+  Test: less than 1
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+
+Monkey 3:
+  Starting items:
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0";
+
+    #[test]
+    fn test_rules() {
+        let input = RULES;
+
+        let mut monkeys = parse_input(input);
+        round(&mut monkeys, &Relief::Divide(1)).unwrap();
+
+        // 10 -> 4 goes to 3, 70 -> 34 is thrown to 2 and 99 -> 49 to 1.
+        let counts = monkeys.iter().map(|m| m.n_inspected).collect_vec();
+        assert_eq!(counts, [3, 1, 1, 1]);
+        assert_eq!(monkeys[0].items, [49, 34, 4]);
+    }
+
+    #[test]
+    fn test_unreducible_worry() {
+        assert_eq!(part_two(RULES), None);
+        assert_eq!(
+            worry_modulus(&parse_input(RULES)).unwrap_err(),
+            "monkey 0: worry cannot be reduced through a subtraction or division"
+        );
+
+        let monkeys = parse_input(&RULES.replace("(old - 1) / 2", "old * 2 + 1"));
+        assert_eq!(
+            worry_modulus(&monkeys).unwrap_err(),
+            "monkey 0: worry cannot be reduced before a comparison"
+        );
+        assert!(extrapolated_monkey_business(&monkeys, 10000).is_err());
+        assert!(inspections_per_round(&monkeys, 10).is_err());
+    }
+
+    #[test]
+    fn test_malformed_monkeys() {
+        let input = advent_of_code::read_file("examples", 11);
+
+        let e = parse_monkeys(&input.replace("old * 19", "old * * 19")).unwrap_err();
+        assert_eq!(
+            e,
+            r#"line 3, column 26: expected `old`, a number or `(`, found "*""#
+        );
+
+        let e =
+            parse_monkeys(&input.replace("throw to monkey 3", "throw to monkey 4")).unwrap_err();
+        assert_eq!(e, "monkey 0 cannot throw to monkey 4");
+
+        let e = parse_monkeys(&input.replace("Monkey 2", "Monkey 5")).unwrap_err();
+        assert_eq!(e, "monkey 5 is listed as monkey 2");
+
+        assert!(parse_monkeys(&input.replace("divisible by 13", "divisible by 0")).is_err());
+    }
+
+    #[test]
+    fn test_round() {
+        let input = advent_of_code::read_file("examples", 11);
+        let mut monkeys = parse_input(&input);
+        for _ in 0..20 {
+            round(&mut monkeys, &Relief::Divide(3)).unwrap();
+        }
+
+        let counts = monkeys.iter().map(|m| m.n_inspected).collect_vec();
+        assert_eq!(counts, [101, 95, 7, 105]);
+        assert_eq!(monkeys[0].items, [10, 12, 14, 26, 34]);
+    }
+
+    /// Part 2 the way it was simulated before [`round`]: items are kept in `Vec`s and taken
+    /// from the front one at a time.
+    fn reference_part_two(monkeys: &[Monkey], lcm: u64) -> u64 {
+        let mut items = monkeys
+            .iter()
+            .map(|m| m.items.iter().copied().collect_vec())
+            .collect_vec();
+        let mut inspected = vec![0; monkeys.len()];
+
+        for _ in 0..10000 {
+            for (i, m) in monkeys.iter().enumerate() {
+                while !items[i].is_empty() {
+                    let worry = m.operation.eval_mod(items[i].remove(0), lcm);
+                    inspected[i] += 1;
+                    items[m.test.check(worry)].push(worry);
+                }
+            }
+        }
+
+        inspected.into_iter().sorted().rev().take(2).product()
+    }
+
+    fn timed<T>(f: impl Fn() -> T) -> (T, time::Duration) {
+        let start = time::Instant::now();
+        let result = f();
+        (result, start.elapsed())
+    }
+
+    /// Run with `cargo test --release --bin 11 bench -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "benchmark on the real input, meant for release builds"]
+    fn bench_part_two() {
+        let input = advent_of_code::read_file("inputs", 11);
+        let lcm = worry_modulus(&parse_input(&input)).unwrap();
+
+        // interleaved, so that both see the same load on the machine.
+        let (mut before, mut after) = (time::Duration::MAX, time::Duration::MAX);
+        for _ in 0..30 {
+            let (expected, t) = timed(|| reference_part_two(&parse_input(&input), lcm));
+            before = before.min(t);
+            let (answer, t) = timed(|| part_two(&input).unwrap());
+            after = after.min(t);
+            assert_eq!(answer, expected);
+        }
+        println!("part 2, best of 30: {before:?} with Vecs, {after:?} with round");
+
+        assert!(
+            after <= before.mul_f64(1.1),
+            "round regressed: {after:?} > {before:?}"
+        );
+    }
 }
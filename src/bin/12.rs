@@ -1,4 +1,4 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use itertools::Itertools;
 use ndarray::*;
@@ -31,83 +31,142 @@ fn parse_input(input: &str) -> (Grid, Pos, Pos) {
     (grid, start, end)
 }
 
-fn neighbors(g: &Grid, (i, j): Pos) -> Vec<Pos> {
-    let x = g[[i, j]];
-    let mut n = vec![];
+fn neighbors(g: &Grid, (i, j): Pos) -> impl Iterator<Item = Pos> {
+    let (h, w) = g.dim();
+
+    [
+        (i.wrapping_sub(1), j),
+        (i, j.wrapping_sub(1)),
+        (i + 1, j),
+        (i, j + 1),
+    ]
+    .into_iter()
+    .filter(move |&(i, j)| i < h && j < w)
+}
 
-    if 0 < i && g[[i - 1, j]] <= x + 1 {
-        n.push((i - 1, j));
-    }
+/// Whether one can climb from elevation `from` to elevation `to` in one step.
+fn can_climb(from: u8, to: u8) -> bool {
+    to <= from + 1
+}
 
-    if 0 < j && g[[i, j - 1]] <= x + 1 {
-        n.push((i, j - 1));
-    }
+/// Shortest route from `start` to the nearest position satisfying `is_end`, moving only where
+/// `can_step(from, to)` allows it.
+fn bfs(
+    grid: &Grid,
+    start: Pos,
+    is_end: impl Fn(Pos) -> bool,
+    can_step: impl Fn(u8, u8) -> bool,
+) -> Option<Vec<Pos>> {
+    let mut dist = Array2::from_elem(grid.dim(), u32::MAX);
+    let mut to_visit = VecDeque::from([start]);
+    dist[start] = 0;
+
+    while let Some(curr) = to_visit.pop_front() {
+        if is_end(curr) {
+            return Some(trace_back(grid, &dist, curr, &can_step));
+        }
 
-    if i < g.len_of(Axis(0)) - 1 && g[[i + 1, j]] <= x + 1 {
-        n.push((i + 1, j));
+        for n in neighbors(grid, curr) {
+            if dist[n] == u32::MAX && can_step(grid[curr], grid[n]) {
+                dist[n] = dist[curr] + 1;
+                to_visit.push_back(n);
+            }
+        }
     }
 
-    if j < g.len_of(Axis(1)) - 1 && g[[i, j + 1]] <= x + 1 {
-        n.push((i, j + 1));
+    None
+}
+
+/// Walks the distance grid back down from `end` to the start of the search.
+fn trace_back(
+    grid: &Grid,
+    dist: &Array2<u32>,
+    end: Pos,
+    can_step: impl Fn(u8, u8) -> bool,
+) -> Vec<Pos> {
+    let mut route = vec![end];
+    let mut curr = end;
+
+    while dist[curr] > 0 {
+        curr = neighbors(grid, curr)
+            .find(|&n| dist[n] == dist[curr] - 1 && can_step(grid[n], grid[curr]))
+            .unwrap();
+        route.push(curr);
     }
 
-    n
+    route.reverse();
+    route
 }
 
-fn bfs(grid: &Grid, start: Pos, end: Pos) -> Option<u32> {
-    let mut visited = HashSet::new();
-    let mut to_visit = VecDeque::from([(start, 0)]);
-
-    while let Some((curr, dist)) = to_visit.pop_front() {
-        if visited.contains(&curr) {
-            continue;
-        }
+/// The shortest route from `S` to `E`.
+fn route_from_start(grid: &Grid, start: Pos, end: Pos) -> Option<Vec<Pos>> {
+    bfs(grid, start, |p| p == end, can_climb)
+}
 
-        visited.insert(curr);
+/// The shortest route from any lowest square to `E`, found by descending from `E`.
+fn route_from_lowest(grid: &Grid, end: Pos) -> Option<Vec<Pos>> {
+    let mut route = bfs(
+        grid,
+        end,
+        |p| grid[p] == b'a',
+        |from, to| can_climb(to, from),
+    )?;
+    route.reverse();
+
+    Some(route)
+}
 
-        if curr == end {
-            return Some(dist);
-        }
+/// The map with each square of `route` showing the direction of the next step.
+fn render_route(grid: &Grid, route: &[Pos]) -> String {
+    let mut map = Array2::from_elem(grid.dim(), '.');
+
+    for (&(i0, j0), &(i1, j1)) in route.iter().tuple_windows() {
+        map[[i0, j0]] = match (i1 as isize - i0 as isize, j1 as isize - j0 as isize) {
+            (-1, 0) => '^',
+            (1, 0) => 'v',
+            (0, -1) => '<',
+            _ => '>',
+        };
+    }
 
-        for n in neighbors(&grid, curr) {
-            to_visit.push_back((n, dist + 1));
-        }
+    if let Some(&end) = route.last() {
+        map[end] = 'E';
     }
 
-    None
+    map.rows()
+        .into_iter()
+        .map(|row| row.iter().collect::<String>())
+        .join("\n")
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
     let (grid, start, end) = parse_input(input);
 
-    bfs(&grid, start, end)
+    route_from_start(&grid, start, end).map(|r| r.len() as u32 - 1)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
     let (grid, _, end) = parse_input(input);
-    let mut starts = vec![];
 
-    for i in 0..grid.len_of(Axis(0)) {
-        for j in 0..grid.len_of(Axis(1)) {
-            if grid[[i, j]] == 'a' as u8 {
-                starts.push((i, j));
-            }
-        }
-    }
-
-    Some(
-        starts
-            .iter()
-            .filter_map(|&s| bfs(&grid, s, end))
-            .min()
-            .unwrap(),
-    )
+    route_from_lowest(&grid, end).map(|r| r.len() as u32 - 1)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 12);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--route") {
+        let (grid, start, end) = parse_input(input);
+        let routes = [
+            route_from_start(&grid, start, end),
+            route_from_lowest(&grid, end),
+        ];
+
+        for route in routes.into_iter().flatten() {
+            println!("\n{}", render_route(&grid, &route));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -125,4 +184,31 @@ mod tests {
         let input = advent_of_code::read_file("examples", 12);
         assert_eq!(part_two(&input), Some(29));
     }
+
+    #[test]
+    fn test_routes() {
+        let input = advent_of_code::read_file("examples", 12);
+        let (grid, start, end) = parse_input(&input);
+
+        for (route, len) in [
+            (route_from_start(&grid, start, end).unwrap(), 31),
+            (route_from_lowest(&grid, end).unwrap(), 29),
+        ] {
+            assert_eq!(route.len(), len + 1);
+            assert_eq!(route.last(), Some(&end));
+            assert_eq!(grid[route[0]], b'a');
+            assert!(route.iter().tuple_windows().all(|(&a, &b)| {
+                neighbors(&grid, a).contains(&b) && can_climb(grid[a], grid[b])
+            }));
+        }
+
+        let map = render_route(&grid, &route_from_start(&grid, start, end).unwrap());
+        assert_eq!(map, ">>vv<<<<\n..vvv<<^\n..vv>E^^\n..v>>>^^\n..>>>>>^");
+    }
+
+    #[test]
+    fn test_unreachable() {
+        assert_eq!(part_one("SazE"), None);
+        assert_eq!(part_two("SazE"), None);
+    }
 }
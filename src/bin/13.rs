@@ -1,113 +1,86 @@
-use std::*;
+use std::{cmp, fmt};
 
-#[derive(Debug, PartialEq)]
+use advent_of_code::helpers::parse::{self, Cursor};
+use itertools::Itertools;
+use Elt::*;
+
+#[derive(Debug, Clone)]
 enum Elt {
     Int(u32),
     List(Vec<Elt>),
 }
 
-impl PartialOrd for Elt {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        use cmp::Ordering::*;
+/// Packets are equal when neither is in the right order before the other, so `3 == [3]`.
+impl PartialEq for Elt {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
 
+impl Eq for Elt {}
+
+impl Ord for Elt {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
         match (self, other) {
-            (Int(a), Int(b)) => a.partial_cmp(b),
-            (List(a), List(b)) => {
-                let mut a = a.iter();
-                let mut b = b.iter();
-                loop {
-                    match (a.next(), b.next()) {
-                        (None, None) => return Some(Equal),
-                        (None, Some(_)) => return Some(Less),
-                        (Some(_), None) => return Some(Greater),
-                        (Some(a), Some(b)) => match a.partial_cmp(b).unwrap() {
-                            Equal => (),
-                            res => return Some(res),
-                        },
-                    }
-                }
-            }
-            (Int(a), List(_)) => List(vec![Int(*a)]).partial_cmp(other),
-            (List(_), Int(b)) => self.partial_cmp(&List(vec![Int(*b)])),
+            (Int(a), Int(b)) => a.cmp(b),
+            (List(a), List(b)) => a.iter().cmp(b.iter()),
+            (Int(a), List(_)) => List(vec![Int(*a)]).cmp(other),
+            (List(_), Int(b)) => self.cmp(&List(vec![Int(*b)])),
         }
     }
 }
 
-// fn in_right_order(a: &Elt, b: &Elt) -> Option<bool> {
-//     match (a, b) {
-//         (Int(a), Int(b)) => {
-//             if a == b {
-//                 None
-//             } else {
-//                 Some(a < b)
-//             }
-//         }
-//         (List(a), List(b)) => {
-//             let mut a = a.iter();
-//             let mut b = b.iter();
-
-//             loop {
-//                 match (a.next(), b.next()) {
-//                     (None, None) => return None,
-//                     (None, Some(_)) => return Some(true),
-//                     (Some(_), None) => return Some(false),
-//                     (Some(a), Some(b)) => match in_right_order(a, b) {
-//                         None => (),
-//                         Some(ans) => return Some(ans),
-//                     },
-//                 }
-//             }
-//         }
-//         (Int(a), List(_)) => return in_right_order(&List(vec![Int(*a)]), b),
-//         (List(_), Int(b)) => return in_right_order(a, &List(vec![Int(*b)])),
-//     }
-// }
-
-use itertools::Itertools;
-use Elt::*;
+impl PartialOrd for Elt {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-fn parse_list(chars: &mut str::Chars) -> Vec<Elt> {
-    let mut l = vec![];
-
-    loop {
-        let next = chars.next().unwrap();
-        match next.to_digit(10) {
-            Some(n) => {
-                l.push(Int(n));
-            }
-            None => {
-                if next == ',' {
-                } else if next == '[' {
-                    l.push(List(parse_list(chars)));
-                } else {
-                    assert_eq!(next, ']');
-                    return l;
-                }
-            }
+impl fmt::Display for Elt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Int(n) => write!(f, "{n}"),
+            List(l) => write!(f, "[{}]", l.iter().join(",")),
         }
     }
 }
 
-fn parse_input(input: &str) -> Vec<(Elt, Elt)> {
-    let mut lines = input.lines();
-    let mut pairs = vec![];
+fn parse_packet(c: &mut Cursor) -> parse::Result<Elt> {
+    if !c.eat("[") {
+        return Ok(Int(c.int()?));
+    }
 
-    while let Some((a, b)) = lines.next_tuple() {
-        let mut a_chars = a.chars();
-        let mut b_chars = b.chars();
+    let items = if c.peek() == Some(']') {
+        vec![]
+    } else {
+        c.list(",", parse_packet)?
+    };
+    c.tag("]")?;
 
-        lines.next();
+    Ok(List(items))
+}
 
-        assert_eq!(a_chars.next().unwrap(), '[');
-        assert_eq!(b_chars.next().unwrap(), '[');
+/// Parses a whole line holding a single list packet.
+fn parse_line(block: &mut Cursor) -> parse::Result<Elt> {
+    let mut l = block
+        .line()
+        .ok_or_else(|| block.error("expected a packet"))?;
+    if l.peek() != Some('[') {
+        return Err(l.error("a packet must be a list"));
+    }
 
-        let a = parse_list(&mut a_chars);
-        let b = parse_list(&mut b_chars);
+    let packet = parse_packet(&mut l)?;
+    l.end()?;
 
-        pairs.push((List(a), List(b)));
-    }
+    Ok(packet)
+}
 
-    pairs
+fn parse_pairs(input: &str) -> parse::Result<Vec<(Elt, Elt)>> {
+    parse::each_block(input, |b| Ok((parse_line(b)?, parse_line(b)?)))
+}
+
+fn parse_input(input: &str) -> Vec<(Elt, Elt)> {
+    parse_pairs(input).unwrap()
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -116,32 +89,132 @@ pub fn part_one(input: &str) -> Option<u32> {
 
     for (i, (a, b)) in pairs.iter().enumerate() {
         if a < b {
-            // println!("{a:?} < {b:?}; i = {}", i + 1);
             total += i + 1;
         }
-        // if in_right_order(a, b).unwrap() {
-        //     // println!("{a:?} < {b:?}; i = {}", i + 1);
-        //     total += i + 1;
-        // }
     }
 
     Some(total as u32)
 }
 
+fn dividers() -> [Elt; 2] {
+    [
+        List(vec![List(vec![Int(2)])]),
+        List(vec![List(vec![Int(6)])]),
+    ]
+}
+
+/// All packets, including the dividers, in the right order.
+fn sorted_packets(pairs: Vec<(Elt, Elt)>) -> Vec<Elt> {
+    let mut packets = pairs.into_iter().flat_map(|(a, b)| [a, b]).collect_vec();
+    packets.extend(dividers());
+    packets.sort_unstable();
+
+    packets
+}
+
+/// Product of the 1-based positions of the divider packets in the sorted `packets`.
+fn decoder_key(packets: &[Elt]) -> usize {
+    dividers()
+        .iter()
+        .map(|d| packets.binary_search(d).unwrap() + 1)
+        .product()
+}
+
+/// Same as [`decoder_key`], but only counts the packets that sort before each divider.
+fn decoder_key_by_counting(pairs: &[(Elt, Elt)]) -> usize {
+    let [first, second] = dividers();
+    let packets = pairs.iter().flat_map(|(a, b)| [a, b]);
+
+    let before_first = packets.clone().filter(|&p| *p < first).count();
+    let before_second = packets.filter(|&p| *p < second).count();
+
+    // the first divider also sorts before the second one.
+    (before_first + 1) * (before_second + 2)
+}
+
 pub fn part_two(input: &str) -> Option<u32> {
-    None
+    Some(decoder_key_by_counting(&parse_input(input)) as u32)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 13);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--sorted") {
+        let packets = sorted_packets(parse_input(input));
+        for p in packets.iter() {
+            println!("{p}");
+        }
+        println!("decoder key: {}", decoder_key(&packets));
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
     use super::*;
 
+    fn arbitrary_elt(g: &mut Gen, depth: usize) -> Elt {
+        if depth == 0 || bool::arbitrary(g) {
+            return Int(u32::arbitrary(g) % 1000);
+        }
+
+        let len = usize::arbitrary(g) % 5;
+        List((0..len).map(|_| arbitrary_elt(g, depth - 1)).collect())
+    }
+
+    impl Arbitrary for Elt {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let len = usize::arbitrary(g) % 5;
+            List((0..len).map(|_| arbitrary_elt(g, 4)).collect())
+        }
+    }
+
+    /// Structural equality, unlike `==` which treats `3` and `[3]` as the same packet.
+    fn same(a: &Elt, b: &Elt) -> bool {
+        match (a, b) {
+            (Int(a), Int(b)) => a == b,
+            (List(a), List(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b)),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        fn round_trip(p: Elt) -> bool {
+            let s = p.to_string();
+            parse_line(&mut Cursor::new(&s)).is_ok_and(|q| same(&p, &q))
+        }
+
+        QuickCheck::new().quickcheck(round_trip as fn(Elt) -> bool);
+    }
+
+    #[test]
+    fn test_parse_multi_digit() {
+        let pairs = parse_input("[10,[2,[]],345]\n[[]]");
+        assert!(same(
+            &pairs[0].0,
+            &List(vec![Int(10), List(vec![Int(2), List(vec![])]), Int(345)])
+        ));
+        assert_eq!(pairs[0].0.to_string(), "[10,[2,[]],345]");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, line, col) in [
+            ("[1,2\n[3]", 1, 5),
+            ("[1,2]]\n[3]", 1, 6),
+            ("[1,,2]\n[3]", 1, 4),
+            ("[1]\n4", 2, 1),
+            ("[1]", 1, 4),
+        ] {
+            let e = parse_pairs(input).unwrap_err();
+            assert_eq!((e.line, e.col), (line, col), "{input:?}: {e}");
+        }
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 13);
@@ -151,6 +224,25 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 13);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(140));
+    }
+
+    #[test]
+    fn test_decoder_key_by_sorting() {
+        let input = advent_of_code::read_file("examples", 13);
+        let packets = sorted_packets(parse_input(&input));
+
+        assert_eq!(packets.len(), 18);
+        assert!(packets.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(decoder_key(&packets), 140);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let int = Int(3);
+        let list = List(vec![Int(3)]);
+        assert_eq!(int, list);
+        assert!(List(vec![]) < List(vec![List(vec![])]));
+        assert!(List(vec![Int(1), Int(9)]) < List(vec![Int(2)]));
     }
 }
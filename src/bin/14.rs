@@ -1,149 +1,290 @@
+use advent_of_code::helpers::grid::{BBox, Point};
 use itertools::Itertools;
-use ndarray::*;
-use std::*;
-
-#[derive(Debug)]
-struct Point {
-    x: usize,
-    y: usize,
-}
+use ndarray::Array2;
+use std::{io::Write, *};
 
 type Path = Vec<Point>;
 
-type Grid = Array2<u8>;
-
-fn print_grid(grid: &Grid) {
-    for x in 0..grid.len_of(Axis(0)) {
-        for y in 0..grid.len_of(Axis(1)) {
-            let c = match grid[[x, y]] {
-                0 => '.',
-                1 => '#',
-                2 => 'o',
-                _ => panic!(),
-            };
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Wall,
+    Sand,
+}
 
-            print!("{c}");
-        }
+const SOURCE: Point = Point::new(500, 0);
+
+struct Cave {
+    /// Indexed by `[y, x]` relative to `bbox.min`.
+    cells: Array2<Option<Cell>>,
+    /// Covers the rocks and the source, with room for sand to spill over the sides, or to spread
+    /// over the whole floor if there is one.
+    bbox: BBox,
+    source: Point,
+    /// Lowest row a grain can come to rest in: one above the floor, or the first row of the abyss.
+    bottom: isize,
+    with_floor: bool,
+    n_sand: u32,
+}
 
-        println!();
-    }
+/// How `--replay` shows the sand coming to rest.
+struct Replay {
+    /// Grains dropped between two frames.
+    batch: usize,
+    delay: time::Duration,
+    final_only: bool,
 }
 
-const EMPTY: u8 = 0;
-const WALL: u8 = 1;
-const SAND: u8 = 2;
+fn parse_point(s: &str) -> Result<Point, String> {
+    let (x, y) = s
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .map_err(|_| format!("bad coordinate in {s:?}"))
+        })
+        .collect_tuple()
+        .ok_or_else(|| format!("expected x,y, found {s:?}"))?;
+
+    Ok(Point::new(x?, y?))
+}
 
 fn parse_input(input: &str) -> Vec<Path> {
-    let mut paths = vec![];
-    for l in input.lines() {
-        let mut path = vec![];
-        for p in l.split(" -> ") {
-            let (x, y) = p.split(",").collect_tuple().unwrap();
-            path.push(Point {
-                x: x.parse().unwrap(),
-                y: y.parse().unwrap(),
-            });
+    input
+        .lines()
+        .map(|l| l.split(" -> ").map(|p| parse_point(p).unwrap()).collect())
+        .collect()
+}
+
+impl Cave {
+    fn new(paths: &[Path], source: Point, with_floor: bool) -> Cave {
+        let points = paths.iter().flatten().chain([&source]);
+        let (min_x, max_x) = points.clone().map(|p| p.x).minmax().into_option().unwrap();
+        let (min_y, max_y) = points.map(|p| p.y).minmax().into_option().unwrap();
+
+        let bottom = max_y + 1;
+        let spread = if with_floor { bottom - source.y } else { 0 };
+        let bbox = BBox {
+            min: Point::new(cmp::min(min_x - 1, source.x - spread), min_y),
+            max: Point::new(cmp::max(max_x + 1, source.x + spread), bottom),
+        };
+
+        let mut cave = Cave {
+            cells: Array2::from_elem((bbox.height(), bbox.width()), None),
+            bbox,
+            source,
+            bottom,
+            with_floor,
+            n_sand: 0,
+        };
+
+        for path in paths {
+            for (last, p) in path.iter().tuple_windows() {
+                for x in cmp::min(last.x, p.x)..=cmp::max(last.x, p.x) {
+                    for y in cmp::min(last.y, p.y)..=cmp::max(last.y, p.y) {
+                        cave.set(Point::new(x, y), Cell::Wall);
+                    }
+                }
+            }
         }
 
-        paths.push(path);
+        cave
     }
 
-    paths
-}
+    fn index(&self, p: &Point) -> Option<[usize; 2]> {
+        let min = self.bbox.min;
+        self.bbox
+            .contains(p)
+            .then(|| [(p.y - min.y) as usize, (p.x - min.x) as usize])
+    }
 
-fn build_grid(paths: &Vec<Path>, with_floor: bool) -> Grid {
-    let max_x = paths.iter().flatten().map(|p| p.x).max().unwrap();
-    let max_y = paths.iter().flatten().map(|p| p.y).max().unwrap();
+    fn get(&self, p: &Point) -> Option<Cell> {
+        self.index(p).and_then(|i| self.cells[i])
+    }
 
-    let mut grid = Array2::zeros((1000, 1000));
+    fn set(&mut self, p: Point, cell: Cell) {
+        let i = self.index(&p).unwrap();
+        if cell == Cell::Sand && self.cells[i] != Some(Cell::Sand) {
+            self.n_sand += 1;
+        }
 
-    for path in paths {
-        let mut p_it = path.iter();
-        let mut last = p_it.next().unwrap();
+        self.cells[i] = Some(cell);
+    }
 
-        while let Some(p) = p_it.next() {
-            let start_x = cmp::min(last.x, p.x);
-            let start_y = cmp::min(last.y, p.y);
-            let end_x = cmp::max(last.x, p.x);
-            let end_y = cmp::max(last.y, p.y);
+    /// Drops a grain from the source and returns where it comes to rest, or `None` if the source
+    /// is blocked or the grain falls into the abyss.
+    fn drop_grain(&mut self) -> Option<Point> {
+        if self.get(&self.source).is_some() {
+            return None;
+        }
 
-            for x in start_x..=end_x {
-                for y in start_y..=end_y {
-                    grid[[x, y]] = WALL;
-                }
+        let mut pos = self.source;
+        while pos.y < self.bottom {
+            let next = [0, -1, 1]
+                .into_iter()
+                .map(|dx| pos.offset(dx, 1))
+                .find(|p| self.get(p).is_none());
+
+            match next {
+                // only possible without a floor: the grain spills over the side of the rocks.
+                Some(next) if !self.bbox.contains(&next) => return None,
+                Some(next) => pos = next,
+                None => break,
             }
-
-            last = p;
         }
-    }
 
-    if with_floor {
-        for x in 0..grid.len_of(Axis(0)) {
-            grid[[x, max_y + 2]] = WALL;
+        if pos.y == self.bottom && !self.with_floor {
+            return None;
         }
+
+        self.set(pos, Cell::Sand);
+        Some(pos)
     }
 
-    grid
-}
+    /// The cave one row per line, with the source as `+` and the floor, if any, below it.
+    fn render(&self) -> String {
+        let mut rows = self
+            .cells
+            .rows()
+            .into_iter()
+            .zip(self.bbox.min.y..)
+            .map(|(row, y)| {
+                row.iter()
+                    .zip(self.bbox.min.x..)
+                    .map(|(c, x)| match c {
+                        None if Point::new(x, y) == self.source => '+',
+                        None => '.',
+                        Some(Cell::Wall) => '#',
+                        Some(Cell::Sand) => 'o',
+                    })
+                    .collect::<String>()
+            });
 
-fn put_one_sand(grid: &mut Grid) -> bool {
-    let mut pos = Point { x: 500, y: 0 };
-    loop {
-        if pos.x == 0 || pos.x == grid.len_of(Axis(0)) - 1 || pos.y == grid.len_of(Axis(1)) - 1 {
-            return false;
+        let mut out = rows.join("\n");
+        if self.with_floor {
+            out.push('\n');
+            out.push_str(&"#".repeat(self.bbox.width()));
         }
 
-        if grid[[pos.x, pos.y + 1]] == EMPTY {
-            pos.y += 1;
-            continue;
-        } else if grid[[pos.x - 1, pos.y + 1]] == EMPTY {
-            pos.x -= 1;
-            pos.y += 1;
-            continue;
-        } else if grid[[pos.x + 1, pos.y + 1]] == EMPTY {
-            pos.x += 1;
-            pos.y += 1;
-            continue;
+        out
+    }
+
+    /// Drops grains until they stop coming to rest, redrawing the cave in place after each batch.
+    fn replay(&mut self, replay: &Replay) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        let mut drawn_lines = 0;
+        // with only the final frame to show, there is nothing to pace.
+        let batch = if replay.final_only {
+            usize::MAX
         } else {
-            break;
+            replay.batch
+        };
+
+        loop {
+            let dropped = iter::from_fn(|| self.drop_grain()).take(batch).count();
+            let done = dropped < batch;
+
+            if drawn_lines > 0 {
+                write!(out, "\x1b[{drawn_lines}A")?;
+            }
+
+            let frame = format!("{}\n{} grains at rest\n", self.render(), self.n_sand);
+            drawn_lines = frame.lines().count();
+            out.write_all(frame.as_bytes())?;
+            out.flush()?;
+
+            if done {
+                return Ok(());
+            }
+
+            thread::sleep(replay.delay);
         }
     }
 
-    grid[[pos.x, pos.y]] = SAND;
-    true
-}
+    /// Drops grains one at a time until they stop coming to rest.
+    fn simulate(&mut self) -> u32 {
+        while self.drop_grain().is_some() {}
+
+        self.n_sand
+    }
 
-fn num_sand_collected(grid: &mut Grid) -> u32 {
-    while grid[[500, 0]] == EMPTY && put_one_sand(grid) {}
+    /// With a floor, sand ends up in every free cell below one of the three cells above it that
+    /// holds sand, so the final state can be filled in row by row without dropping any grains.
+    fn fill(&mut self) -> u32 {
+        assert!(self.with_floor, "only a cave with a floor fills up");
 
-    let mut num_sand = 0;
-    for &c in grid.iter() {
-        if c == SAND {
-            num_sand += 1
+        if self.get(&self.source).is_none() {
+            self.set(self.source, Cell::Sand);
         }
-    }
 
-    num_sand
+        for y in self.source.y + 1..=self.bottom {
+            let spread = y - self.source.y;
+            for x in self.source.x - spread..=self.source.x + spread {
+                let p = Point::new(x, y);
+                let fed =
+                    (-1..=1).any(|dx| self.get(&Point::new(x + dx, y - 1)) == Some(Cell::Sand));
+
+                if fed && self.get(&p).is_none() {
+                    self.set(p, Cell::Sand);
+                }
+            }
+        }
+
+        self.n_sand
+    }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
     let paths = parse_input(input);
-    let mut grid = build_grid(&paths, false);
 
-    Some(num_sand_collected(&mut grid))
+    Some(Cave::new(&paths, SOURCE, false).simulate())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
     let paths = parse_input(input);
-    let mut grid = build_grid(&paths, true);
 
-    Some(num_sand_collected(&mut grid))
+    Some(Cave::new(&paths, SOURCE, true).fill())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let mut args = pico_args::Arguments::from_env();
+    let source = args.opt_value_from_fn("--source", parse_point).unwrap();
+    if let Some(source) = source {
+        let paths = parse_input(input);
+        let abyss = Cave::new(&paths, source, false).simulate();
+        let floor = Cave::new(&paths, source, true).fill();
+
+        println!(
+            "from {},{}: {abyss} grains above the abyss, {floor} on the floor",
+            source.x, source.y
+        );
+    }
+
+    if let Some(mode) = args.opt_value_from_str::<_, String>("--replay").unwrap() {
+        let with_floor = match mode.as_str() {
+            "abyss" => false,
+            "floor" => true,
+            _ => panic!("--replay takes abyss or floor, not {mode:?}"),
+        };
+        let replay = Replay {
+            batch: args
+                .opt_value_from_str("--batch")
+                .unwrap()
+                .unwrap_or(1)
+                .max(1),
+            delay: time::Duration::from_millis(
+                args.opt_value_from_str("--delay").unwrap().unwrap_or(20),
+            ),
+            final_only: args.contains("--final"),
+        };
+
+        Cave::new(&parse_input(input), source.unwrap_or(SOURCE), with_floor)
+            .replay(&replay)
+            .unwrap();
+    }
 }
 
 #[cfg(test)]
@@ -161,4 +302,66 @@ mod tests {
         let input = advent_of_code::read_file("examples", 14);
         assert_eq!(part_two(&input), Some(93));
     }
+
+    #[test]
+    fn test_render() {
+        let paths = parse_input(&advent_of_code::read_file("examples", 14));
+        let mut cave = Cave::new(&paths, SOURCE, false);
+        assert_eq!(cave.render().lines().next(), Some(".......+...."));
+
+        cave.simulate();
+        let expected = [
+            ".......+....",
+            "............",
+            ".......o....",
+            "......ooo...",
+            ".....#ooo##.",
+            "....o#ooo#..",
+            "...###ooo#..",
+            ".....oooo#..",
+            "..o.ooooo#..",
+            ".#########..",
+            "............",
+        ];
+        assert_eq!(cave.render(), expected.join("\n"));
+
+        let mut cave = Cave::new(&paths, SOURCE, true);
+        cave.fill();
+        let rendered = cave.render();
+        assert_eq!(rendered.lines().count(), 12);
+        assert_eq!(
+            rendered.lines().next(),
+            Some(&*format!("{}o{}", ".".repeat(10), ".".repeat(10)))
+        );
+        assert_eq!(rendered.lines().last(), Some(&*"#".repeat(21)));
+    }
+
+    #[test]
+    fn test_fill_matches_simulation() {
+        let paths = parse_input(&advent_of_code::read_file("examples", 14));
+
+        for source in [
+            SOURCE,
+            Point::new(497, 0),
+            Point::new(503, 2),
+            Point::new(490, 8),
+        ] {
+            let simulated = Cave::new(&paths, source, true).simulate();
+            assert_eq!(
+                Cave::new(&paths, source, true).fill(),
+                simulated,
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sources() {
+        let paths = parse_input(&advent_of_code::read_file("examples", 14));
+
+        // blocked by rock, and left of every rock.
+        assert_eq!(Cave::new(&paths, Point::new(498, 5), false).simulate(), 0);
+        assert_eq!(Cave::new(&paths, Point::new(490, 0), false).simulate(), 0);
+        assert_eq!(Cave::new(&paths, Point::new(490, 0), true).fill(), 106);
+    }
 }
@@ -1,43 +1,158 @@
-// #[macro_use]
-// extern crate scan_fmt;
-
-// struct Pos {
-//     x: i32,
-//     y: i32,
-// }
-
-// struct Reading {
-//     sensor: Pos,
-//     beacon: Pos,
-// }
-
-// impl Reading {
-//     fn area_covered(&self) -> i32 {
-//         let dx = self.sensor.x.abs_diff(self.beacon.x);
-//         let dy = self.sensor.y.abs_diff(self.beacon.y);
-//     }
-// }
-
-// fn parse_input(input: &str) {
-//     input.lines().map(|l| {
-//         let (sx, sy, bx, by) = scan_fmt!(
-//             l,
-//             "Sensor at x={d}, y={d}: closest beacon is at x={d}, y={d}",
-//             i32,
-//             i32,
-//             i32,
-//             i32
-//         )
-//         .unwrap();
-//     });
-// }
+use std::collections::HashSet;
+
+use advent_of_code::helpers::parse::{self, Cursor};
+use itertools::Itertools;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Pos {
+    x: i64,
+    y: i64,
+}
+
+impl Pos {
+    fn dist(&self, other: &Pos) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+struct Reading {
+    sensor: Pos,
+    beacon: Pos,
+    radius: i64,
+}
+
+impl Reading {
+    fn covers(&self, p: &Pos) -> bool {
+        self.sensor.dist(p) <= self.radius
+    }
+
+    /// The inclusive range of x covered in row `y`, if any.
+    fn row_coverage(&self, y: i64) -> Option<(i64, i64)> {
+        let half_width = self.radius - (self.sensor.y - y).abs();
+        if half_width < 0 {
+            None
+        } else {
+            Some((self.sensor.x - half_width, self.sensor.x + half_width))
+        }
+    }
+}
+
+fn parse_pos(c: &mut Cursor) -> parse::Result<Pos> {
+    c.tag("x=")?;
+    let x = c.int()?;
+    c.tag(", y=")?;
+    let y = c.int()?;
+
+    Ok(Pos { x, y })
+}
+
+fn parse_input(input: &str) -> Vec<Reading> {
+    parse::each_line(input, |l| {
+        l.tag("Sensor at ")?;
+        let sensor = parse_pos(l)?;
+        l.tag(": closest beacon is at ")?;
+        let beacon = parse_pos(l)?;
+
+        Ok(Reading {
+            sensor,
+            beacon,
+            radius: sensor.dist(&beacon),
+        })
+    })
+    .unwrap()
+}
+
+/// Merges overlapping or adjacent inclusive ranges.
+fn merge(mut ranges: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+fn covered_positions(input: &str, row: i64) -> Option<u32> {
+    let readings = parse_input(input);
+
+    let covered = merge(
+        readings
+            .iter()
+            .filter_map(|r| r.row_coverage(row))
+            .collect(),
+    );
+    let n_covered: i64 = covered.iter().map(|(start, end)| end - start + 1).sum();
+
+    let beacons_in_row = readings
+        .iter()
+        .map(|r| r.beacon)
+        .filter(|b| b.y == row)
+        .collect::<HashSet<_>>();
+
+    Some((n_covered - beacons_in_row.len() as i64) as u32)
+}
+
+/// The only uncovered position with both coordinates in `0..=bound`.
+///
+/// Such a position must sit just outside the diamonds of several sensors, or on the edge of the
+/// search area. So we only test where the lines bordering each diamond cross each other or the
+/// edges, instead of every position.
+fn find_beacon(readings: &[Reading], bound: i64) -> Option<Pos> {
+    // lines are `x + y = a` (falling) or `x - y = b` (rising).
+    let mut falling = vec![0, 2 * bound];
+    let mut rising = vec![-bound, bound];
+
+    for r in readings {
+        let Pos { x, y } = r.sensor;
+        falling.extend([x + y - r.radius - 1, x + y + r.radius + 1]);
+        rising.extend([x - y - r.radius - 1, x - y + r.radius + 1]);
+    }
+
+    let crossings = falling
+        .iter()
+        .cartesian_product(rising.iter())
+        .filter(|(a, b)| (*a - *b) % 2 == 0)
+        .map(|(a, b)| Pos {
+            x: (a + b) / 2,
+            y: (a - b) / 2,
+        });
+
+    let edges = falling.iter().chain(rising.iter()).flat_map(|&c| {
+        [0, bound].into_iter().flat_map(move |e| {
+            [
+                Pos { x: e, y: c - e },
+                Pos { x: c - e, y: e },
+                Pos { x: e, y: e - c },
+                Pos { x: c + e, y: e },
+            ]
+        })
+    });
+
+    crossings.chain(edges).find(|p| {
+        (0..=bound).contains(&p.x)
+            && (0..=bound).contains(&p.y)
+            && readings.iter().all(|r| !r.covers(p))
+    })
+}
+
+fn tuning_frequency(input: &str, bound: i64) -> Option<u64> {
+    let readings = parse_input(input);
+    let beacon = find_beacon(&readings, bound)?;
+
+    Some((beacon.x * 4_000_000 + beacon.y) as u64)
+}
 
 pub fn part_one(input: &str) -> Option<u32> {
-    None
+    covered_positions(input, 2_000_000)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+pub fn part_two(input: &str) -> Option<u64> {
+    tuning_frequency(input, 4_000_000)
 }
 
 fn main() {
@@ -50,15 +165,29 @@ fn main() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_merge() {
+        assert_eq!(
+            merge(vec![(5, 8), (0, 2), (3, 4), (7, 10), (12, 12)]),
+            vec![(0, 10), (12, 12)]
+        );
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(covered_positions(&input, 10), Some(26));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(tuning_frequency(&input, 20), Some(56000011));
+    }
+
+    #[test]
+    fn test_find_beacon_in_corner() {
+        let readings = parse_input("Sensor at x=3, y=3: closest beacon is at x=3, y=8");
+        assert_eq!(find_beacon(&readings, 4), Some(Pos { x: 0, y: 0 }));
     }
 }
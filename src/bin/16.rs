@@ -1,15 +1,246 @@
+use std::collections::{HashMap, VecDeque};
+
+use advent_of_code::helpers::{
+    memo::Memo,
+    parse::{self, Cursor},
+};
+
+struct Valve<'a> {
+    name: &'a str,
+    flow: u32,
+    tunnels: Vec<&'a str>,
+}
+
+/// The valves worth opening, with the shortest distances between them.
+///
+/// Valves `0..n` are the ones with a non-zero flow rate, so a set of them fits in a bitmask.
+/// Index `n` is the starting valve `AA`.
+struct Network<'a> {
+    names: Vec<&'a str>,
+    flow: Vec<u32>,
+    dist: Vec<Vec<u32>>,
+}
+
+/// Minute at which each valve is opened.
+type Schedule<'a> = Vec<(u32, &'a str)>;
+
+type PressureMemo = Memo<(usize, u32, u32), u32>;
+
+fn parse_input(input: &str) -> Vec<Valve<'_>> {
+    parse::each_line(input, |l| {
+        l.tag("Valve ")?;
+        let name = l.word()?;
+        l.tag(" has flow rate=")?;
+        let flow = l.int()?;
+        l.tag("; ")?;
+        if !l.eat("tunnels lead to valves ") {
+            l.tag("tunnel leads to valve ")?;
+        }
+        let tunnels = l.list(", ", Cursor::word)?;
+
+        Ok(Valve {
+            name,
+            flow,
+            tunnels,
+        })
+    })
+    .unwrap()
+}
+
+impl<'a> Network<'a> {
+    fn new(valves: &[Valve<'a>]) -> Self {
+        let index: HashMap<&str, usize> = valves
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.name, i))
+            .collect();
+
+        let bfs = |from: usize| {
+            let mut dist = vec![u32::MAX; valves.len()];
+            let mut to_visit = VecDeque::from([from]);
+            dist[from] = 0;
+
+            while let Some(curr) = to_visit.pop_front() {
+                for t in valves[curr].tunnels.iter() {
+                    let next = index[t];
+                    if dist[next] == u32::MAX {
+                        dist[next] = dist[curr] + 1;
+                        to_visit.push_back(next);
+                    }
+                }
+            }
+
+            dist
+        };
+
+        let mut relevant = (0..valves.len())
+            .filter(|&i| valves[i].flow > 0)
+            .collect::<Vec<_>>();
+        relevant.push(index["AA"]);
+
+        let full_dist = relevant.iter().map(|&i| bfs(i)).collect::<Vec<_>>();
+
+        Self {
+            names: relevant.iter().map(|&i| valves[i].name).collect(),
+            flow: relevant.iter().map(|&i| valves[i].flow).collect(),
+            dist: full_dist
+                .iter()
+                .map(|d| relevant.iter().map(|&j| d[j]).collect())
+                .collect(),
+        }
+    }
+
+    fn n_valves(&self) -> usize {
+        self.flow.len() - 1
+    }
+
+    fn start(&self) -> usize {
+        self.n_valves()
+    }
+
+    fn all_valves(&self) -> u32 {
+        (1 << self.n_valves()) - 1
+    }
+
+    /// Valves that can still be reached and opened from `pos` with time to spare, along with the
+    /// time left once they are open.
+    fn next_valves(
+        &self,
+        pos: usize,
+        time: u32,
+        opened: u32,
+    ) -> impl Iterator<Item = (usize, u32)> + '_ {
+        (0..self.n_valves())
+            .filter(move |v| opened & (1 << v) == 0)
+            .filter_map(move |v| {
+                let cost = self.dist[pos][v].checked_add(1)?;
+                (cost < time).then(|| (v, time - cost))
+            })
+    }
+
+    /// Most pressure released in `time` minutes from `pos`, never opening a valve in `opened`.
+    fn max_pressure(&self, memo: &mut PressureMemo, pos: usize, time: u32, opened: u32) -> u32 {
+        memo.get_or_insert_with((pos, time, opened), |memo| {
+            self.next_valves(pos, time, opened)
+                .map(|(v, left)| {
+                    self.flow[v] * left + self.max_pressure(memo, v, left, opened | (1 << v))
+                })
+                .max()
+                .unwrap_or(0)
+        })
+    }
+
+    /// The valve openings that achieve [`Network::max_pressure`] from the start.
+    fn schedule(&self, memo: &mut PressureMemo, time: u32, mut opened: u32) -> Schedule<'a> {
+        let mut schedule = vec![];
+        let mut pos = self.start();
+        let mut left = time;
+
+        loop {
+            let best = self.max_pressure(memo, pos, left, opened);
+            let next = self.next_valves(pos, left, opened).find(|&(v, l)| {
+                self.flow[v] * l + self.max_pressure(memo, v, l, opened | (1 << v)) == best
+            });
+
+            match next {
+                Some((v, l)) if best > 0 => {
+                    schedule.push((time - l, self.names[v]));
+                    pos = v;
+                    left = l;
+                    opened |= 1 << v;
+                }
+                _ => return schedule,
+            }
+        }
+    }
+
+    /// Most pressure released in `time` minutes by opening exactly each set of valves.
+    fn best_per_set(&self, time: u32) -> Vec<u32> {
+        fn visit(
+            net: &Network,
+            best: &mut [u32],
+            pos: usize,
+            time: u32,
+            opened: u32,
+            released: u32,
+        ) {
+            best[opened as usize] = best[opened as usize].max(released);
+
+            for (v, left) in net.next_valves(pos, time, opened) {
+                let released = released + net.flow[v] * left;
+                visit(net, best, v, left, opened | (1 << v), released);
+            }
+        }
+
+        let mut best = vec![0; 1 << self.n_valves()];
+        visit(self, &mut best, self.start(), time, 0, 0);
+
+        best
+    }
+
+    /// Best split of the valves between two agents, as the set opened by the first one.
+    fn best_split(&self, time: u32) -> (u32, u32) {
+        let mut best = self.best_per_set(time);
+
+        // afterwards, `best[set]` allows opening any subset of `set`.
+        for v in 0..self.n_valves() {
+            for set in 0..best.len() {
+                if set & (1 << v) != 0 {
+                    best[set] = best[set].max(best[set ^ (1 << v)]);
+                }
+            }
+        }
+
+        let all = self.all_valves();
+        (0..=all)
+            .map(|mine| (best[mine as usize] + best[(all ^ mine) as usize], mine))
+            .max()
+            .unwrap()
+    }
+}
+
 pub fn part_one(input: &str) -> Option<u32> {
-    None
+    let valves = parse_input(input);
+    let net = Network::new(&valves);
+
+    Some(net.max_pressure(&mut Memo::new(), net.start(), 30, 0))
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    None
+    let valves = parse_input(input);
+    let net = Network::new(&valves);
+
+    Some(net.best_split(26).0)
+}
+
+fn print_schedules(input: &str) {
+    let print = |who: &str, schedule: Schedule| {
+        for (minute, name) in schedule {
+            println!("{who}: open {name} at minute {minute}");
+        }
+    };
+
+    let valves = parse_input(input);
+    let net = Network::new(&valves);
+    let mut memo = Memo::new();
+
+    println!("Part 1:");
+    print("you", net.schedule(&mut memo, 30, 0));
+
+    let (_, mine) = net.best_split(26);
+    println!("Part 2:");
+    print("you", net.schedule(&mut memo, 26, net.all_valves() ^ mine));
+    print("elephant", net.schedule(&mut memo, 26, mine));
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 16);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--schedule") {
+        print_schedules(input);
+    }
 }
 
 #[cfg(test)]
@@ -19,12 +250,31 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(part_one(&input), Some(1651));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(1707));
+    }
+
+    #[test]
+    fn test_schedule() {
+        let input = advent_of_code::read_file("examples", 16);
+        let valves = parse_input(&input);
+        let net = Network::new(&valves);
+
+        assert_eq!(
+            net.schedule(&mut Memo::new(), 30, 0),
+            vec![
+                (2, "DD"),
+                (5, "BB"),
+                (9, "JJ"),
+                (17, "HH"),
+                (21, "EE"),
+                (24, "CC")
+            ]
+        );
     }
 }
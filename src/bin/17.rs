@@ -1,9 +1,166 @@
-pub fn part_one(input: &str) -> Option<u32> {
-    None
+use std::collections::HashMap;
+
+const WIDTH: usize = 7;
+
+/// Rock shapes as rows from the bottom up, with bit `i` set if column `i` is filled.
+/// Each one starts two units away from the left wall.
+const ROCKS: [&[u8]; 5] = [
+    &[0b0111100],
+    &[0b0001000, 0b0011100, 0b0001000],
+    &[0b0011100, 0b0010000, 0b0010000],
+    &[0b0000100, 0b0000100, 0b0000100, 0b0000100],
+    &[0b0001100, 0b0001100],
+];
+
+const LEFT_WALL: u8 = 1;
+const RIGHT_WALL: u8 = 1 << (WIDTH - 1);
+
+#[derive(Clone, Copy)]
+enum Jet {
+    Left,
+    Right,
+}
+
+/// What decides how the rest of the tower grows: the next rock, the next jet, and how far each
+/// column's top lies below the top of the tower.
+#[derive(PartialEq, Eq, Hash)]
+struct Snapshot {
+    rock: usize,
+    jet: usize,
+    skyline: [usize; WIDTH],
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+struct Chamber {
+    rows: Vec<u8>,
+    jets: Vec<Jet>,
+    rock: usize,
+    jet: usize,
+}
+
+fn parse_input(input: &str) -> Vec<Jet> {
+    input
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' => Jet::Left,
+            '>' => Jet::Right,
+            _ => panic!("bad jet {c}"),
+        })
+        .collect()
+}
+
+impl Chamber {
+    fn new(jets: Vec<Jet>) -> Self {
+        Self {
+            rows: vec![],
+            jets,
+            rock: 0,
+            jet: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn collides(&self, rock: &[u8], y: usize) -> bool {
+        rock.iter()
+            .enumerate()
+            .any(|(i, r)| self.rows.get(y + i).is_some_and(|row| row & r != 0))
+    }
+
+    fn drop_rock(&mut self) {
+        let mut rock = ROCKS[self.rock].to_vec();
+        let mut y = self.height() + 3;
+        self.rock = (self.rock + 1) % ROCKS.len();
+
+        loop {
+            let pushed = match self.jets[self.jet] {
+                Jet::Left if rock.iter().all(|r| r & LEFT_WALL == 0) => {
+                    Some(rock.iter().map(|r| r >> 1).collect::<Vec<_>>())
+                }
+                Jet::Right if rock.iter().all(|r| r & RIGHT_WALL == 0) => {
+                    Some(rock.iter().map(|r| r << 1).collect())
+                }
+                _ => None,
+            };
+            self.jet = (self.jet + 1) % self.jets.len();
+
+            if let Some(pushed) = pushed {
+                if !self.collides(&pushed, y) {
+                    rock = pushed;
+                }
+            }
+
+            if y == 0 || self.collides(&rock, y - 1) {
+                break;
+            }
+
+            y -= 1;
+        }
+
+        for (i, r) in rock.into_iter().enumerate() {
+            if y + i == self.rows.len() {
+                self.rows.push(0);
+            }
+
+            self.rows[y + i] |= r;
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut skyline = [self.height(); WIDTH];
+        for (c, depth) in skyline.iter_mut().enumerate() {
+            if let Some(d) = self.rows.iter().rev().position(|row| row & (1 << c) != 0) {
+                *depth = d;
+            }
+        }
+
+        Snapshot {
+            rock: self.rock,
+            jet: self.jet,
+            skyline,
+        }
+    }
+}
+
+/// Height of the tower after `n_rocks` have come to rest.
+///
+/// Once the chamber gets back into a state it has been in before, the rocks dropped since then
+/// will keep repeating with the same gain in height, so we skip over as many repeats as fit.
+fn tower_height(jets: Vec<Jet>, n_rocks: u64) -> u64 {
+    let mut chamber = Chamber::new(jets);
+    let mut seen = HashMap::new();
+    let mut skipped_height = None;
+    let mut dropped = 0;
+
+    while dropped < n_rocks {
+        chamber.drop_rock();
+        dropped += 1;
+
+        if skipped_height.is_some() {
+            continue;
+        }
+
+        let now = (dropped, chamber.height() as u64);
+        if let Some((prev_dropped, prev_height)) = seen.insert(chamber.snapshot(), now) {
+            let cycle_len = dropped - prev_dropped;
+            let cycles = (n_rocks - dropped) / cycle_len;
+
+            dropped += cycles * cycle_len;
+            skipped_height = Some(cycles * (now.1 - prev_height));
+        }
+    }
+
+    chamber.height() as u64 + skipped_height.unwrap_or(0)
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    Some(tower_height(parse_input(input), 2022))
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    Some(tower_height(parse_input(input), 1_000_000_000_000))
 }
 
 fn main() {
@@ -16,15 +173,26 @@ fn main() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_simulation_without_cycles() {
+        let input = advent_of_code::read_file("examples", 17);
+        let mut chamber = Chamber::new(parse_input(&input));
+        for _ in 0..2022 {
+            chamber.drop_rock();
+        }
+
+        assert_eq!(chamber.height(), 3068);
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 17);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(part_one(&input), Some(3068));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 17);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(1514285714288));
     }
 }
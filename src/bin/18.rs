@@ -1,15 +1,141 @@
-pub fn part_one(input: &str) -> Option<u32> {
-    None
+use std::collections::{HashSet, VecDeque};
+
+use advent_of_code::helpers::parse;
+
+type Voxel = [i32; 3];
+
+const DIRECTIONS: [Voxel; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+fn neighbors(v: Voxel) -> impl Iterator<Item = Voxel> {
+    DIRECTIONS
+        .map(|d| [v[0] + d[0], v[1] + d[1], v[2] + d[2]])
+        .into_iter()
+}
+
+fn parse_input(input: &str) -> HashSet<Voxel> {
+    parse::each_line(input, |l| {
+        let coords = l.list(",", |c| c.int())?;
+        coords
+            .try_into()
+            .map_err(|_| l.error("expected three coordinates"))
+    })
+    .unwrap()
+    .into_iter()
+    .collect()
+}
+
+/// The droplet's bounding box, grown by one in every direction so that air surrounds it.
+struct Bounds {
+    min: Voxel,
+    max: Voxel,
+}
+
+impl Bounds {
+    fn padded(cubes: &HashSet<Voxel>) -> Self {
+        let mut min = [i32::MAX; 3];
+        let mut max = [i32::MIN; 3];
+        for c in cubes {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(c[axis] - 1);
+                max[axis] = max[axis].max(c[axis] + 1);
+            }
+        }
+
+        Self { min, max }
+    }
+
+    fn contains(&self, v: &Voxel) -> bool {
+        (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&v[axis]))
+    }
+
+    fn voxels(&self) -> impl Iterator<Item = Voxel> + '_ {
+        (self.min[0]..=self.max[0]).flat_map(move |x| {
+            (self.min[1]..=self.max[1])
+                .flat_map(move |y| (self.min[2]..=self.max[2]).map(move |z| [x, y, z]))
+        })
+    }
+}
+
+/// All air voxels within `bounds` connected to `from`.
+fn flood_fill(cubes: &HashSet<Voxel>, bounds: &Bounds, from: Voxel) -> HashSet<Voxel> {
+    let mut seen = HashSet::from([from]);
+    let mut to_visit = VecDeque::from([from]);
+
+    while let Some(curr) = to_visit.pop_front() {
+        for next in neighbors(curr) {
+            if bounds.contains(&next) && !cubes.contains(&next) && seen.insert(next) {
+                to_visit.push_back(next);
+            }
+        }
+    }
+
+    seen
+}
+
+fn surface_area(cubes: &HashSet<Voxel>) -> usize {
+    cubes
+        .iter()
+        .flat_map(|&c| neighbors(c))
+        .filter(|n| !cubes.contains(n))
+        .count()
+}
+
+fn exterior_surface_area(cubes: &HashSet<Voxel>) -> usize {
+    let bounds = Bounds::padded(cubes);
+    let outside = flood_fill(cubes, &bounds, bounds.min);
+
+    cubes
+        .iter()
+        .flat_map(|&c| neighbors(c))
+        .filter(|n| outside.contains(n))
+        .count()
+}
+
+/// Volumes of the pockets of air trapped inside the droplet.
+fn air_pockets(cubes: &HashSet<Voxel>) -> Vec<usize> {
+    let bounds = Bounds::padded(cubes);
+    let mut filled = flood_fill(cubes, &bounds, bounds.min);
+    let mut pockets = vec![];
+
+    for v in bounds.voxels() {
+        if !cubes.contains(&v) && !filled.contains(&v) {
+            let pocket = flood_fill(cubes, &bounds, v);
+            pockets.push(pocket.len());
+            filled.extend(pocket);
+        }
+    }
+
+    pockets
+}
+
+pub fn part_one(input: &str) -> Option<usize> {
+    Some(surface_area(&parse_input(input)))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+pub fn part_two(input: &str) -> Option<usize> {
+    Some(exterior_surface_area(&parse_input(input)))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 18);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--pockets") {
+        let pockets = air_pockets(&parse_input(input));
+        println!(
+            "{} air pockets, {} voxels of trapped air",
+            pockets.len(),
+            pockets.iter().sum::<usize>()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -19,12 +145,31 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 18);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(part_one(&input), Some(64));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 18);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(58));
+    }
+
+    #[test]
+    fn test_air_pockets() {
+        let input = advent_of_code::read_file("examples", 18);
+        assert_eq!(air_pockets(&parse_input(&input)), vec![1]);
+
+        // a 4x3x3 block with a 2x1x1 hole in the middle
+        let mut cubes = HashSet::new();
+        for x in 0..4 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    cubes.insert([x, y, z]);
+                }
+            }
+        }
+        cubes.remove(&[1, 1, 1]);
+        cubes.remove(&[2, 1, 1]);
+        assert_eq!(air_pockets(&cubes), vec![2]);
     }
 }
@@ -1,14 +1,8 @@
-#[macro_use]
-extern crate scan_fmt;
+use std::{collections::HashMap, thread};
 
-#[macro_use]
-extern crate maplit;
+use advent_of_code::helpers::parse::{self, Cursor, ParseError};
 
-use std::{collections::HashMap, str::Lines};
-
-use itertools::Itertools;
-
-#[derive(Debug, enum_utils::FromStr, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, enum_utils::FromStr, PartialEq, Eq, Hash)]
 #[enumeration(case_insensitive)]
 enum Item {
     Ore,
@@ -19,60 +13,229 @@ enum Item {
 
 type Recipe = HashMap<Item, u32>;
 
-type Blueprint = HashMap<Item, Recipe>;
-
-fn parse_blueprint(lines: &mut Lines) -> Option<Blueprint> {
-    let _ = scan_fmt!(lines.next()?, "Blueprint {}:", u32).unwrap();
-    let mut blueprint: Blueprint = hashmap! {};
-
-    for _ in 0..4 {
-        let line = lines
-            .next()
-            .unwrap()
-            .strip_suffix(".")
-            .unwrap()
-            .split_whitespace()
-            .collect_vec();
-
-        if line.len() == 6 {
-            blueprint.insert(
-                line[1].parse().unwrap(),
-                hashmap! { line[5].parse().unwrap() => line[4].parse().unwrap() },
-            );
-        } else {
-            blueprint.insert(
-                line[1].parse().unwrap(),
-                hashmap! {
-                    line[5].parse().unwrap() => line[4].parse().unwrap(),
-                    line[8].parse().unwrap() => line[7].parse().unwrap(),
-                },
-            );
+const ITEMS: [Item; 4] = [Item::Ore, Item::Clay, Item::Obsidian, Item::Geode];
+
+#[derive(Debug)]
+struct Blueprint {
+    id: u32,
+    robots: HashMap<Item, Recipe>,
+}
+
+/// A blueprint flattened for the search: `costs[robot][item]` is how much of `item` it takes to
+/// build a `robot`.
+struct Factory {
+    costs: [[u32; 4]; 4],
+    max_robots: [u32; 4],
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    time_left: u32,
+    robots: [u32; 4],
+    stock: [u32; 4],
+}
+
+/// Consumes `word`, after any amount of whitespace.
+fn keyword(c: &mut Cursor, word: &str) -> parse::Result<()> {
+    c.skip_ws();
+    c.tag(word)
+}
+
+fn parse_recipe(c: &mut Cursor) -> parse::Result<Recipe> {
+    let mut recipe = Recipe::new();
+
+    loop {
+        c.skip_ws();
+        let n = c.int()?;
+        c.skip_ws();
+        recipe.insert(c.parse(Cursor::word)?, n);
+
+        c.skip_ws();
+        if !c.eat("and") {
+            break;
         }
     }
+    c.tag(".")?;
 
-    Some(blueprint)
+    Ok(recipe)
 }
 
-fn parse_input(input: &str) -> Vec<Blueprint> {
-    let mut lines = input.lines();
+fn parse_robots<'a>(
+    c: &mut Cursor<'a>,
+    start: &Cursor<'a>,
+) -> parse::Result<HashMap<Item, Recipe>> {
+    let mut robots = HashMap::new();
+
+    loop {
+        c.skip_ws();
+        let before = *c;
+        if !c.eat("Each") {
+            break;
+        }
+
+        c.skip_ws();
+        let robot: Item = c.parse(Cursor::word)?;
+        keyword(c, "robot")?;
+        keyword(c, "costs")?;
+
+        if robots.insert(robot, parse_recipe(c)?).is_some() {
+            return Err(before.error(format!("second recipe for the {robot:?} robot")));
+        }
+    }
+
+    match ITEMS.into_iter().find(|item| !robots.contains_key(item)) {
+        Some(missing) => Err(start.error(format!("no recipe for the {missing:?} robot"))),
+        None => Ok(robots),
+    }
+}
+
+/// Parses a blueprint laid out either on one line or with one robot per line.
+fn parse_blueprint(c: &mut Cursor) -> parse::Result<Blueprint> {
+    let start = *c;
+    c.tag("Blueprint")?;
+    c.skip_ws();
+    let id = c.int()?;
+    c.tag(":")?;
+
+    match parse_robots(c, &start) {
+        Ok(robots) => Ok(Blueprint { id, robots }),
+        Err(e) => Err(ParseError {
+            msg: format!("blueprint {id}: {}", e.msg),
+            ..e
+        }),
+    }
+}
+
+fn parse_blueprints(input: &str) -> parse::Result<Vec<Blueprint>> {
+    let mut c = Cursor::new(input);
     let mut blueprints = vec![];
-    while let Some(b) = parse_blueprint(&mut lines) {
-        blueprints.push(b);
-        let _ = lines.next();
+
+    c.skip_ws();
+    while !c.is_empty() {
+        blueprints.push(parse_blueprint(&mut c)?);
+        c.skip_ws();
+    }
+
+    Ok(blueprints)
+}
+
+fn parse_input(input: &str) -> Vec<Blueprint> {
+    parse_blueprints(input).unwrap()
+}
+
+impl Factory {
+    fn new(blueprint: &Blueprint) -> Self {
+        let mut costs = [[0; 4]; 4];
+        for (robot, recipe) in blueprint.robots.iter() {
+            for (item, &n) in recipe.iter() {
+                costs[*robot as usize][*item as usize] = n;
+            }
+        }
+
+        // there is no point in producing more of a resource per minute than can be spent in one,
+        // since only one robot gets built per minute.
+        let mut max_robots = [u32::MAX; 4];
+        for item in [Item::Ore, Item::Clay, Item::Obsidian] {
+            max_robots[item as usize] = costs.iter().map(|c| c[item as usize]).max().unwrap();
+        }
+
+        Self { costs, max_robots }
+    }
+
+    /// The state right after building `robot`, waiting first until there is enough to pay for it.
+    /// `None` if it cannot be built before the time is up.
+    fn build(&self, state: &State, robot: Item) -> Option<State> {
+        let cost = &self.costs[robot as usize];
+
+        let mut wait = 0;
+        for ((&c, &stock), &robots) in cost.iter().zip(&state.stock).zip(&state.robots) {
+            if c > stock {
+                if robots == 0 {
+                    return None;
+                }
+                wait = wait.max((c - stock).div_ceil(robots));
+            }
+        }
+
+        // a robot built in the last minute never gets to collect anything.
+        let elapsed = wait + 1;
+        if elapsed >= state.time_left {
+            return None;
+        }
+
+        let mut next = *state;
+        next.time_left -= elapsed;
+        for ((stock, &robots), &c) in next.stock.iter_mut().zip(&state.robots).zip(cost) {
+            *stock = *stock + robots * elapsed - c;
+        }
+        next.robots[robot as usize] += 1;
+
+        Some(next)
     }
 
-    blueprints
+    fn max_geodes(&self, time: u32) -> u32 {
+        let start = State {
+            time_left: time,
+            robots: [1, 0, 0, 0],
+            stock: [0; 4],
+        };
+
+        let mut best = 0;
+        self.search(&start, &mut best);
+
+        best
+    }
+
+    /// Depth-first search over which robot to build next, updating `best` with the most geodes
+    /// found so far.
+    fn search(&self, state: &State, best: &mut u32) {
+        let geode = Item::Geode as usize;
+        let t = state.time_left;
+
+        let idle = state.stock[geode] + state.robots[geode] * t;
+        *best = (*best).max(idle);
+
+        // even building a geode robot every remaining minute can't beat `best`.
+        if idle + t * t.saturating_sub(1) / 2 <= *best {
+            return;
+        }
+
+        for robot in ITEMS.into_iter().rev() {
+            if state.robots[robot as usize] >= self.max_robots[robot as usize] {
+                continue;
+            }
+
+            if let Some(next) = self.build(state, robot) {
+                self.search(&next, best);
+            }
+        }
+    }
+}
+
+/// The most geodes each blueprint can open in `time` minutes, each solved on its own thread.
+fn max_geodes(blueprints: &[Blueprint], time: u32) -> Vec<u32> {
+    thread::scope(|s| {
+        let handles = blueprints
+            .iter()
+            .map(|b| s.spawn(move || Factory::new(b).max_geodes(time)))
+            .collect::<Vec<_>>();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
     let blueprints = parse_input(input);
-    println!("{:?}", blueprints);
+    let geodes = max_geodes(&blueprints, 24);
 
-    None
+    Some(blueprints.iter().zip(geodes).map(|(b, g)| b.id * g).sum())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    None
+    let blueprints = parse_input(input);
+    let n = blueprints.len().min(3);
+
+    Some(max_geodes(&blueprints[..n], 32).into_iter().product())
 }
 
 fn main() {
@@ -88,12 +251,44 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 19);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(part_one(&input), Some(33));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 19);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(56 * 62));
+    }
+
+    #[test]
+    fn test_single_line_layout() {
+        let input = advent_of_code::read_file("examples", 19);
+        let single_line = input
+            .split("\n\n")
+            .map(|b| b.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(part_one(&single_line), Some(33));
+    }
+
+    #[test]
+    fn test_malformed_blueprint() {
+        let missing = "\
+Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each geode robot costs 3 ore and 12 obsidian.";
+        let e = parse_blueprints(missing).unwrap_err();
+        assert_eq!((e.line, e.col), (2, 1));
+        assert_eq!(e.msg, "blueprint 2: no recipe for the Obsidian robot");
+
+        let bad_cost = "Blueprint 7: Each ore robot costs 4 ore. Each clay robot costs two ore.";
+        let e = parse_blueprints(bad_cost).unwrap_err();
+        assert!(e.msg.starts_with("blueprint 7: expected an integer"), "{e}");
+    }
+
+    #[test]
+    fn test_max_geodes() {
+        let input = advent_of_code::read_file("examples", 19);
+        assert_eq!(max_geodes(&parse_input(&input), 24), vec![9, 12]);
     }
 }
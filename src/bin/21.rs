@@ -1,6 +1,6 @@
 use std::{collections::HashMap, str::FromStr};
 
-use itertools::Itertools;
+use advent_of_code::helpers::parse::{self, Cursor};
 
 #[derive(Debug, Clone, Copy)]
 enum Operator {
@@ -163,26 +163,28 @@ fn print_monkeys(monkeys: &HashMap<&str, Equation>) {
 }
 
 fn parse_input(input: &str) -> HashMap<&str, Equation> {
-    let mut monkeys = HashMap::new();
-
-    for line in input.lines() {
-        let name = &line[..4];
-
-        let eq = &line[6..].split_whitespace().collect_vec();
-        let eq = if eq.len() == 1 {
-            Equation::Const(eq[0].parse().unwrap())
-        } else {
-            Equation::Formula(
-                Value::Monkey(eq[0]),
-                eq[1].parse().unwrap(),
-                Value::Monkey(eq[2]),
-            )
+    parse::each_line(input, |l| {
+        let (name, mut value) = l.key_value(": ")?;
+
+        let eq = match value.peek() {
+            Some(c) if c.is_ascii_digit() || c == '-' => Equation::Const(value.int()?),
+            _ => {
+                let lhs = value.word()?;
+                value.skip_ws();
+                let op = value.parse(Cursor::token)?;
+                value.skip_ws();
+                let rhs = value.word()?;
+
+                Equation::Formula(Value::Monkey(lhs), op, Value::Monkey(rhs))
+            }
         };
+        value.end()?;
 
-        monkeys.insert(name, eq);
-    }
-
-    monkeys
+        Ok((name, eq))
+    })
+    .unwrap()
+    .into_iter()
+    .collect()
 }
 
 pub fn part_one(input: &str) -> Option<i64> {
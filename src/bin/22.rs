@@ -1,15 +1,345 @@
-pub fn part_one(input: &str) -> Option<u32> {
-    None
+use std::collections::{HashMap, VecDeque};
+
+use advent_of_code::helpers::{
+    grid::{Point, SparseGrid},
+    parse::{self, Cursor},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Open,
+    Wall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Facing {
+    Right,
+    Down,
+    Left,
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    Forward(u32),
+    Left,
+    Right,
+}
+
+const FACINGS: [Facing; 4] = [Facing::Right, Facing::Down, Facing::Left, Facing::Up];
+
+impl Facing {
+    fn turn(self, m: Move) -> Self {
+        let turns = match m {
+            Move::Forward(_) => 0,
+            Move::Right => 1,
+            Move::Left => 3,
+        };
+
+        FACINGS[(self as usize + turns) % 4]
+    }
+
+    fn delta(self) -> Point {
+        match self {
+            Facing::Right => Point::new(1, 0),
+            Facing::Down => Point::new(0, 1),
+            Facing::Left => Point::new(-1, 0),
+            Facing::Up => Point::new(0, -1),
+        }
+    }
+
+    fn arrow(self) -> char {
+        match self {
+            Facing::Right => '>',
+            Facing::Down => 'v',
+            Facing::Left => '<',
+            Facing::Up => '^',
+        }
+    }
+}
+
+type Vec3 = [isize; 3];
+
+fn neg(v: Vec3) -> Vec3 {
+    v.map(|c| -c)
+}
+
+fn dot(a: Vec3, b: Vec3) -> isize {
+    (0..3).map(|i| a[i] * b[i]).sum()
+}
+
+/// Where a face of the net ends up once folded: its outward normal, and the directions that
+/// right and down on the map point to.
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    normal: Vec3,
+    right: Vec3,
+    down: Vec3,
+}
+
+impl Face {
+    fn dir(&self, facing: Facing) -> Vec3 {
+        match facing {
+            Facing::Right => self.right,
+            Facing::Down => self.down,
+            Facing::Left => neg(self.right),
+            Facing::Up => neg(self.down),
+        }
+    }
+
+    /// The face next to this one on the net in direction `facing`, folded over the shared edge.
+    fn fold(&self, facing: Facing) -> Face {
+        let Face {
+            normal,
+            right,
+            down,
+        } = *self;
+
+        match facing {
+            Facing::Right => Face {
+                normal: right,
+                right: neg(normal),
+                down,
+            },
+            Facing::Left => Face {
+                normal: neg(right),
+                right: normal,
+                down,
+            },
+            Facing::Down => Face {
+                normal: down,
+                right,
+                down: neg(normal),
+            },
+            Facing::Up => Face {
+                normal: neg(down),
+                right,
+                down: normal,
+            },
+        }
+    }
+}
+
+/// The map folded into a cube, with faces keyed by their position on the net in units of `size`.
+struct Cube {
+    size: isize,
+    faces: HashMap<Point, Face>,
+}
+
+struct Board {
+    tiles: SparseGrid<Tile>,
+    path: Vec<Move>,
+}
+
+fn parse_path(c: &mut Cursor) -> parse::Result<Vec<Move>> {
+    let mut path = vec![];
+
+    while !c.is_empty() {
+        path.push(if c.eat("L") {
+            Move::Left
+        } else if c.eat("R") {
+            Move::Right
+        } else {
+            Move::Forward(c.int()?)
+        });
+    }
+
+    Ok(path)
+}
+
+fn parse_input(input: &str) -> Board {
+    let mut c = Cursor::new(input);
+    let mut tiles = SparseGrid::new();
+
+    for (y, l) in c.lines().take_while(|l| !l.is_empty()).enumerate() {
+        for (x, ch) in l.rest().chars().enumerate() {
+            let tile = match ch {
+                '.' => Tile::Open,
+                '#' => Tile::Wall,
+                _ => continue,
+            };
+            tiles.insert(Point::new(x as isize, y as isize), tile);
+        }
+    }
+
+    let mut path = c.line().expect("missing path");
+    let path = parse_path(&mut path).unwrap();
+
+    Board { tiles, path }
+}
+
+impl Cube {
+    fn fold(tiles: &SparseGrid<Tile>) -> Self {
+        let size = ((tiles.len() / 6) as f64).sqrt() as isize;
+        assert_eq!(6 * size * size, tiles.len() as isize, "not a cube net");
+
+        let on_net = |p: &Point| tiles.contains(&Point::new(p.x * size, p.y * size));
+        let first = tiles.points().min_by_key(|p| (p.y, p.x)).unwrap();
+        let first = Point::new(first.x / size, first.y / size);
+
+        let mut faces = HashMap::from([(
+            first,
+            Face {
+                normal: [0, 0, 1],
+                right: [1, 0, 0],
+                down: [0, 1, 0],
+            },
+        )]);
+        let mut to_visit = VecDeque::from([first]);
+
+        while let Some(curr) = to_visit.pop_front() {
+            let face = faces[&curr];
+            for facing in FACINGS {
+                let next = curr + facing.delta();
+                if on_net(&next) && !faces.contains_key(&next) {
+                    faces.insert(next, face.fold(facing));
+                    to_visit.push_back(next);
+                }
+            }
+        }
+
+        assert_eq!(faces.len(), 6, "not a cube net");
+        Self { size, faces }
+    }
+
+    /// Where walking off the edge of a face from `pos` leads to.
+    ///
+    /// Positions are placed on a cube centred on the origin, in coordinates doubled so that tile
+    /// centres are whole numbers: faces lie at `±size` and tiles at odd offsets from a face centre.
+    fn wrap(&self, pos: Point, facing: Facing) -> (Point, Facing) {
+        let n = self.size;
+        let net_pos = Point::new(pos.x.div_euclid(n), pos.y.div_euclid(n));
+        let from = self.faces[&net_pos];
+        let (col, row) = (pos.x - net_pos.x * n, pos.y - net_pos.y * n);
+
+        let dir = from.dir(facing);
+        let to_pos = *self.faces.iter().find(|(_, f)| f.normal == dir).unwrap().0;
+        let to = self.faces[&to_pos];
+
+        // step over the edge: out of `from`'s plane and into `to`'s.
+        let p: Vec3 = std::array::from_fn(|i| {
+            from.normal[i] * n
+                + from.right[i] * (2 * col + 1 - n)
+                + from.down[i] * (2 * row + 1 - n)
+                + dir[i]
+                - from.normal[i]
+        });
+
+        let col = (dot(p, to.right) + n - 1) / 2;
+        let row = (dot(p, to.down) + n - 1) / 2;
+        let facing = FACINGS
+            .into_iter()
+            .find(|&f| to.dir(f) == neg(from.normal))
+            .unwrap();
+
+        (Point::new(to_pos.x * n + col, to_pos.y * n + row), facing)
+    }
+}
+
+impl Board {
+    fn start(&self) -> Point {
+        *self
+            .tiles
+            .iter()
+            .filter(|(p, t)| p.y == 0 && **t == Tile::Open)
+            .min_by_key(|(p, _)| p.x)
+            .unwrap()
+            .0
+    }
+
+    /// Wraps around to the far side of the map, as if it were flat.
+    fn wrap_flat(&self, pos: Point, facing: Facing) -> (Point, Facing) {
+        let mut pos = pos;
+        while self.tiles.contains(&(pos - facing.delta())) {
+            pos = pos - facing.delta();
+        }
+
+        (pos, facing)
+    }
+
+    /// Follows the path, calling `wrap` to step off the map. Returns every position reached along
+    /// with the facing there, so the last one is where the path ends.
+    fn walk(&self, wrap: impl Fn(Point, Facing) -> (Point, Facing)) -> Vec<(Point, Facing)> {
+        let mut trace = vec![(self.start(), Facing::Right)];
+
+        for &m in self.path.iter() {
+            let (mut pos, mut facing) = *trace.last().unwrap();
+
+            match m {
+                Move::Forward(steps) => {
+                    for _ in 0..steps {
+                        let next = pos + facing.delta();
+                        let next = match self.tiles.contains(&next) {
+                            true => (next, facing),
+                            false => wrap(pos, facing),
+                        };
+
+                        if self.tiles.get(&next.0) == Some(&Tile::Wall) {
+                            break;
+                        }
+
+                        (pos, facing) = next;
+                        trace.push(next);
+                    }
+                }
+                _ => trace.push((pos, facing.turn(m))),
+            }
+        }
+
+        trace
+    }
+
+    /// The map with the path drawn on it, marking each position with the last facing there.
+    fn render_trace(&self, trace: &[(Point, Facing)]) -> String {
+        let mut grid = SparseGrid::new();
+        for (&p, &t) in self.tiles.iter() {
+            grid.insert(p, if t == Tile::Wall { '#' } else { '.' });
+        }
+        for &(p, facing) in trace {
+            grid.insert(p, facing.arrow());
+        }
+
+        grid.render(|c| *c.unwrap_or(&' '))
+    }
+}
+
+fn password(trace: &[(Point, Facing)]) -> usize {
+    let (pos, facing) = trace.last().unwrap();
+    1000 * (pos.y as usize + 1) + 4 * (pos.x as usize + 1) + *facing as usize
+}
+
+pub fn part_one(input: &str) -> Option<usize> {
+    let board = parse_input(input);
+    Some(password(&board.walk(|p, f| board.wrap_flat(p, f))))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+pub fn part_two(input: &str) -> Option<usize> {
+    let board = parse_input(input);
+    let cube = Cube::fold(&board.tiles);
+
+    Some(password(&board.walk(|p, f| cube.wrap(p, f))))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 22);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--trace") {
+        let board = parse_input(input);
+        let cube = Cube::fold(&board.tiles);
+
+        println!("Part 1:");
+        println!(
+            "{}",
+            board.render_trace(&board.walk(|p, f| board.wrap_flat(p, f)))
+        );
+        println!("Part 2:");
+        println!(
+            "{}",
+            board.render_trace(&board.walk(|p, f| cube.wrap(p, f)))
+        );
+    }
 }
 
 #[cfg(test)]
@@ -19,12 +349,63 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 22);
-        assert_eq!(part_one(&input), None);
+        assert_eq!(part_one(&input), Some(6032));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 22);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(5031));
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let mut c = Cursor::new("10R5L42");
+        assert_eq!(
+            parse_path(&mut c),
+            Ok(vec![
+                Move::Forward(10),
+                Move::Right,
+                Move::Forward(5),
+                Move::Left,
+                Move::Forward(42)
+            ])
+        );
+    }
+
+    /// Walking off every edge of every face and straight back must return to the same tile.
+    fn assert_edges_glue_back(net: &str) {
+        let board = parse_input(&format!("{net}\n\n1"));
+        let cube = Cube::fold(&board.tiles);
+
+        for &p in board.tiles.points() {
+            for facing in FACINGS {
+                if board.tiles.contains(&(p + facing.delta())) {
+                    continue;
+                }
+
+                let (q, f) = cube.wrap(p, facing);
+                assert!(
+                    board.tiles.contains(&q),
+                    "{p:?} {facing:?} went off the map"
+                );
+
+                let back = f.turn(Move::Right).turn(Move::Right);
+                let (r, g) = cube.wrap(q, back);
+                assert_eq!((r, g.turn(Move::Right).turn(Move::Right)), (p, facing));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_nets() {
+        let example = advent_of_code::read_file("examples", 22);
+        assert_edges_glue_back(example.split("\n\n").next().unwrap());
+
+        // the layout of the real input.
+        assert_edges_glue_back("  ....\n  ....\n  ..\n  ..\n....\n....\n..\n..");
+        // a cross, and a staircase.
+        assert_edges_glue_back("  ..\n  ..\n......\n......\n  ..\n  ..\n  ..\n  ..");
+        assert_edges_glue_back("..\n..\n....\n....\n  ....\n  ....\n    ..\n    ..");
     }
 }
@@ -1,15 +1,7 @@
-#![feature(variant_count)]
-#![feature(generators)]
-#![feature(iter_from_generator)]
-
-use std::{
-    cmp::{max, min},
-    collections::HashMap,
-};
+use std::collections::HashMap;
 
+use advent_of_code::helpers::grid::{Point, SparseGrid};
 use enum_utils::IterVariants;
-use itertools::Itertools;
-use maplit::hashmap;
 
 #[derive(Clone, IterVariants)]
 enum Dir {
@@ -24,10 +16,10 @@ use Dir::*;
 impl Dir {
     fn delta(&self) -> (isize, isize) {
         match self {
-            North => (-1, 0),
-            South => (1, 0),
-            West => (0, -1),
-            East => (0, 1),
+            North => (0, -1),
+            South => (0, 1),
+            West => (-1, 0),
+            East => (1, 0),
         }
     }
 
@@ -37,69 +29,36 @@ impl Dir {
             West | East => [North, South],
         }
     }
-}
-
-#[derive(PartialEq, Eq, Hash, Clone)]
-struct Pos {
-    x: isize,
-    y: isize,
-}
 
-impl Pos {
-    fn adj(&self, dir: &Dir) -> Self {
-        let (dx, dy) = dir.delta();
-
-        Self {
-            x: self.x + dx,
-            y: self.y + dy,
-        }
-    }
-
-    fn neighbors(&self) -> impl Iterator<Item = Pos> + '_ {
-        std::iter::from_generator(|| {
-            for dx in -1..=1 {
-                for dy in -1..=1 {
-                    if !(dx == 0 && dy == 0) {
-                        yield Pos {
-                            x: self.x + dx,
-                            y: self.y + dy,
-                        }
-                    }
-                }
-            }
-        })
+    fn adj(&self, pos: &Point) -> Point {
+        let (dx, dy) = self.delta();
+        pos.offset(dx, dy)
     }
 }
 
-struct Elf {
-    pos: Pos,
-}
-
 struct State {
-    elfs: HashMap<Pos, Elf>,
+    elfs: SparseGrid<()>,
     dirs: Vec<Dir>,
 }
 impl State {
     fn step(&mut self) -> bool {
-        let mut proposals_from = hashmap! {};
+        let mut proposals_from: HashMap<Point, Vec<Point>> = HashMap::new();
 
         // step 1
-        for (_, e) in self.elfs.iter() {
-            let any_neighbors = e.pos.neighbors().any(|p| self.elfs.contains_key(&p));
+        for pos in self.elfs.points() {
+            let any_neighbors = pos.neighbors().any(|p| self.elfs.contains(&p));
 
             if any_neighbors {
                 for dir_to_try in self.dirs.iter() {
-                    let can_move = !self.elfs.contains_key(&e.pos.adj(dir_to_try))
+                    let target = dir_to_try.adj(pos);
+                    let can_move = !self.elfs.contains(&target)
                         && dir_to_try
                             .orthogonal()
                             .iter()
-                            .all(|d| !self.elfs.contains_key(&e.pos.adj(dir_to_try).adj(d)));
+                            .all(|d| !self.elfs.contains(&d.adj(&target)));
 
                     if can_move {
-                        proposals_from
-                            .entry(e.pos.adj(dir_to_try))
-                            .or_insert(vec![])
-                            .push(e.pos.clone());
+                        proposals_from.entry(target).or_default().push(*pos);
 
                         break;
                     }
@@ -110,13 +69,10 @@ impl State {
         let mut any_moved = false;
 
         // step 2
-        for (pos, from) in proposals_from.iter() {
+        for (pos, from) in proposals_from.into_iter() {
             if from.len() == 1 {
-                let from = &from[0];
-                let mut elf = self.elfs.remove(from).unwrap();
-
-                elf.pos = pos.clone();
-                self.elfs.insert(pos.clone(), elf);
+                self.elfs.remove(&from[0]);
+                self.elfs.insert(pos, ());
 
                 any_moved = true;
             }
@@ -128,70 +84,25 @@ impl State {
         any_moved
     }
 
-    fn elf_bbox(&self) -> (Pos, Pos) {
-        let mut min_x = 0;
-        let mut min_y = 0;
-        let mut max_x = 0;
-        let mut max_y = 0;
-
-        for (p, _) in self.elfs.iter() {
-            min_x = min(min_x, p.x);
-            min_y = min(min_y, p.y);
-            max_x = max(max_x, p.x);
-            max_y = max(max_y, p.y);
-        }
-
-        (Pos { x: min_x, y: min_y }, Pos { x: max_x, y: max_y })
-    }
-
-    fn captured_area(&self) -> isize {
-        let (min, max) = self.elf_bbox();
-
-        (max.x - min.x + 1) * (max.y - min.y + 1)
-    }
-
     fn print(&self) {
-        let (min, max) = self.elf_bbox();
-
-        for x in min.x..=max.x {
-            for y in min.y..=max.y {
-                if self.elfs.contains_key(&Pos { x, y }) {
-                    print!("#");
-                } else {
-                    print!(".")
-                }
-            }
-
-            println!();
-        }
+        println!(
+            "{}",
+            self.elfs.render(|e| if e.is_some() { '#' } else { '.' })
+        );
     }
 }
 
 fn parse_input(input: &str) -> State {
-    let mut elfs = hashmap! {};
-
-    let as_vec = input
+    let elfs = input
         .lines()
         .enumerate()
-        .map(|(x, l)| {
-            l.chars()
-                .enumerate()
-                .filter_map(|(y, c)| match c {
-                    '#' => Some(Elf {
-                        pos: Pos {
-                            x: x as isize,
-                            y: y as isize,
-                        },
-                    }),
-                    _ => None,
-                })
-                .collect_vec()
+        .flat_map(|(y, l)| {
+            l.chars().enumerate().filter_map(move |(x, c)| match c {
+                '#' => Some((Point::new(x as isize, y as isize), ())),
+                _ => None,
+            })
         })
-        .concat();
-
-    for e in as_vec {
-        elfs.insert(e.pos.clone(), e);
-    }
+        .collect();
 
     State {
         elfs,
@@ -205,7 +116,7 @@ pub fn part_one(input: &str) -> Option<u32> {
         state.step();
     }
 
-    Some((state.captured_area() - state.elfs.len() as isize) as u32)
+    Some(state.elfs.count_empty() as u32)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
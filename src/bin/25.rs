@@ -1,106 +1,263 @@
-use std::str::FromStr;
+use std::{
+    cmp::Ordering,
+    fmt, iter,
+    marker::PhantomData,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
 
-use itertools::Itertools;
+/// The symbols of a balanced base, from the most negative digit to the most positive one.
+///
+/// There must be an odd number of them, so that the middle one stands for zero.
+trait Base {
+    const DIGITS: &'static str;
 
-struct Snafu {
+    fn base() -> i64 {
+        Self::DIGITS.chars().count() as i64
+    }
+
+    /// The largest digit; digits range over `-max_digit()..=max_digit()`.
+    fn max_digit() -> i64 {
+        Self::base() / 2
+    }
+}
+
+struct Quinary;
+
+impl Base for Quinary {
+    const DIGITS: &'static str = "=-012";
+}
+
+/// A number in balanced base `B`, of any length.
+struct Balanced<B: Base> {
+    /// Least significant first, without zeros at the most significant end.
     digits: Vec<i8>,
+    base: PhantomData<B>,
 }
 
-impl Snafu {
-    fn to_int(&self) -> i64 {
-        self.digits.iter().fold(0, |acc, d| acc * 5 + (*d as i64))
+type Snafu = Balanced<Quinary>;
+
+impl<B: Base> Balanced<B> {
+    fn zero() -> Self {
+        Self::from_digits(vec![])
     }
 
-    fn max_repr(num_digits: usize) -> i64 {
-        let mut max_repr = 0;
-        for _ in 0..num_digits {
-            max_repr = max_repr * 5 + 2;
+    fn from_digits(mut digits: Vec<i8>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
         }
 
-        max_repr
+        Self {
+            digits,
+            base: PhantomData,
+        }
     }
 
-    fn min_repr(num_digits: usize) -> i64 {
-        let mut max_repr = 0;
-        for _ in 0..num_digits {
-            max_repr = max_repr * 5 - 2;
-        }
+    /// Builds a number from digits that may be out of range, carrying the excess upwards.
+    fn normalize(values: impl IntoIterator<Item = i64>) -> Self {
+        let (base, max) = (B::base(), B::max_digit());
+        let mut values = values.into_iter();
+        let mut digits = vec![];
+        let mut carry = 0;
 
-        max_repr
-    }
+        loop {
+            let v = match values.next() {
+                Some(v) => v + carry,
+                None if carry != 0 => carry,
+                None => break,
+            };
 
-    fn of_int(int: i64) -> Self {
-        let mut num_digits = 1;
-        while Self::max_repr(num_digits) < int {
-            num_digits += 1;
+            carry = (v + max).div_euclid(base);
+            digits.push((v - carry * base) as i8);
         }
 
-        let mut s = Snafu {
-            digits: vec![0i8; num_digits],
-        };
+        Self::from_digits(digits)
+    }
 
-        for i in 0..num_digits {
-            for d in -2..=2 {
-                s.digits[i] = d;
+    fn signum(&self) -> i8 {
+        self.digits.last().map_or(0, |d| d.signum())
+    }
+}
 
-                let diff = int - s.to_int();
-                let min = Self::min_repr(num_digits - i - 1);
-                let max = Self::max_repr(num_digits - i - 1);
-                if min <= diff && diff <= max {
-                    break;
-                }
-            }
+impl<B: Base> From<i64> for Balanced<B> {
+    fn from(n: i64) -> Self {
+        let base = B::base();
+        let mut n = n as i128;
+        let mut digits = vec![];
+
+        while n != 0 {
+            let d = (n + B::max_digit() as i128).rem_euclid(base as i128) - B::max_digit() as i128;
+            digits.push(d as i8);
+            n = (n - d) / base as i128;
         }
 
-        s
+        Self::from_digits(digits)
     }
 }
 
-impl FromStr for Snafu {
+impl<B: Base> TryFrom<&Balanced<B>> for i64 {
+    type Error = String;
+
+    fn try_from(n: &Balanced<B>) -> Result<Self, Self::Error> {
+        n.digits
+            .iter()
+            .rev()
+            .try_fold(0i128, |acc, &d| {
+                acc.checked_mul(B::base() as i128)?.checked_add(d as i128)
+            })
+            .and_then(|v| i64::try_from(v).ok())
+            .ok_or_else(|| format!("{n} does not fit in an i64"))
+    }
+}
+
+impl<B: Base> FromStr for Balanced<B> {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("empty number".to_owned());
+        }
+
         let digits = s
             .chars()
-            .map(|c| match c {
-                '2' => 2,
-                '1' => 1,
-                '0' => 0,
-                '-' => -1,
-                '=' => -2,
-                _ => panic!("bad digit {c}"),
+            .rev()
+            .map(|c| match B::DIGITS.chars().position(|d| d == c) {
+                Some(i) => Ok((i as i64 - B::max_digit()) as i8),
+                None => Err(format!("bad digit {c:?} in {s:?}")),
             })
-            .collect_vec();
+            .collect::<Result<_, _>>()?;
 
-        Ok(Snafu { digits })
+        Ok(Self::from_digits(digits))
     }
 }
 
-impl ToString for Snafu {
-    fn to_string(&self) -> String {
+impl<B: Base> fmt::Display for Balanced<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = |d: i8| {
+            let i = (d as i64 + B::max_digit()) as usize;
+            B::DIGITS.chars().nth(i).unwrap()
+        };
+
+        if self.digits.is_empty() {
+            return write!(f, "{}", symbol(0));
+        }
+
         self.digits
             .iter()
-            .map(|d| match d {
-                2 => '2',
-                1 => '1',
-                0 => '0',
-                -1 => '-',
-                -2 => '=',
-                _ => panic!("bad digit {d}"),
-            })
-            .collect()
+            .rev()
+            .try_for_each(|&d| write!(f, "{}", symbol(d)))
+    }
+}
+
+impl<B: Base> fmt::Debug for Balanced<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl<B: Base> Clone for Balanced<B> {
+    fn clone(&self) -> Self {
+        Self::from_digits(self.digits.clone())
+    }
+}
+
+impl<B: Base> PartialEq for Balanced<B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.digits == other.digits
+    }
+}
+
+impl<B: Base> Eq for Balanced<B> {}
+
+impl<B: Base> Ord for Balanced<B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self - other).signum().cmp(&0)
+    }
+}
+
+impl<B: Base> PartialOrd for Balanced<B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<B: Base> Add for &Balanced<B> {
+    type Output = Balanced<B>;
+
+    fn add(self, rhs: Self) -> Balanced<B> {
+        let len = self.digits.len().max(rhs.digits.len());
+        let digit = |n: &Balanced<B>, i: usize| *n.digits.get(i).unwrap_or(&0) as i64;
+
+        Balanced::normalize((0..len).map(|i| digit(self, i) + digit(rhs, i)))
+    }
+}
+
+impl<B: Base> Neg for &Balanced<B> {
+    type Output = Balanced<B>;
+
+    fn neg(self) -> Balanced<B> {
+        Balanced::from_digits(self.digits.iter().map(|d| -d).collect())
+    }
+}
+
+impl<B: Base> Sub for &Balanced<B> {
+    type Output = Balanced<B>;
+
+    fn sub(self, rhs: Self) -> Balanced<B> {
+        self + &-rhs
+    }
+}
+
+impl<B: Base> Mul for &Balanced<B> {
+    type Output = Balanced<B>;
+
+    fn mul(self, rhs: Self) -> Balanced<B> {
+        let mut products = vec![0; self.digits.len() + rhs.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            for (j, &b) in rhs.digits.iter().enumerate() {
+                products[i + j] += a as i64 * b as i64;
+            }
+        }
+
+        Balanced::normalize(products)
+    }
+}
+
+macro_rules! forward_by_value {
+    ($($trait:ident :: $method:ident),*) => {$(
+        impl<B: Base> $trait for Balanced<B> {
+            type Output = Balanced<B>;
+
+            fn $method(self, rhs: Self) -> Balanced<B> {
+                (&self).$method(&rhs)
+            }
+        }
+    )*};
+}
+
+forward_by_value!(Add::add, Sub::sub, Mul::mul);
+
+impl<B: Base> Neg for Balanced<B> {
+    type Output = Balanced<B>;
+
+    fn neg(self) -> Balanced<B> {
+        -&self
+    }
+}
+
+impl<B: Base> iter::Sum for Balanced<B> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, n| &acc + &n)
     }
 }
 
 pub fn part_one(input: &str) -> Option<String> {
-    let input = input
+    let total = input
         .lines()
         .map(|s| s.parse::<Snafu>().unwrap())
-        .collect_vec();
+        .sum::<Snafu>();
 
-    let total = input.iter().map(|s| s.to_int()).sum::<i64>();
-
-    Some(Snafu::of_int(total).to_string())
+    Some(total.to_string())
 }
 
 pub fn part_two(input: &str) -> Option<String> {
@@ -117,6 +274,16 @@ fn main() {
 mod tests {
     use super::*;
 
+    struct Ternary;
+
+    impl Base for Ternary {
+        const DIGITS: &'static str = "-0+";
+    }
+
+    fn snafu(s: &str) -> Snafu {
+        s.parse().unwrap()
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 25);
@@ -128,4 +295,59 @@ mod tests {
         let input = advent_of_code::read_file("examples", 25);
         assert_eq!(part_two(&input), None);
     }
+
+    #[test]
+    fn test_conversions() {
+        for (n, s) in [
+            (0, "0"),
+            (3, "1="),
+            (8, "2="),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (-2022, "-2--1="),
+        ] {
+            assert_eq!(Snafu::from(n).to_string(), s);
+            assert_eq!(i64::try_from(&snafu(s)), Ok(n));
+        }
+
+        assert_eq!(i64::try_from(&Snafu::from(i64::MIN)), Ok(i64::MIN));
+        assert_eq!(Balanced::<Ternary>::from(8).to_string(), "+0-");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        for a in -60..60 {
+            for b in -60..60 {
+                let (x, y) = (Snafu::from(a), Snafu::from(b));
+                assert_eq!(&x + &y, Snafu::from(a + b));
+                assert_eq!(&x - &y, Snafu::from(a - b));
+                assert_eq!(&x * &y, Snafu::from(a * b));
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+            }
+        }
+
+        assert_eq!(-snafu("1=-0-2"), snafu("-2101="));
+        assert_eq!(snafu("002-"), snafu("2-"));
+    }
+
+    #[test]
+    fn test_beyond_i64() {
+        let big = snafu(&"2".repeat(40));
+        assert!(i64::try_from(&big).is_err());
+
+        let total = [big.clone(), big.clone(), -big].into_iter().sum::<Snafu>();
+        assert_eq!(total.to_string(), "2".repeat(40));
+        assert_eq!((&total * &total).to_string().len(), 80);
+    }
+
+    #[test]
+    fn test_bad_digits() {
+        assert!("".parse::<Snafu>().is_err());
+        assert_eq!(
+            "12a".parse::<Snafu>().unwrap_err(),
+            r#"bad digit 'a' in "12a""#
+        );
+    }
 }
@@ -2,3 +2,8 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
+pub mod grid;
+pub mod math;
+pub mod memo;
+pub mod ocr;
+pub mod parse;
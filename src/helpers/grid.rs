@@ -0,0 +1,260 @@
+use std::collections::{hash_map, BTreeMap, HashMap};
+use std::ops::{Add, Sub};
+
+/// A point on an unbounded 2D grid. `x` grows to the right, `y` grows downwards.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn offset(&self, dx: isize, dy: isize) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
+
+    /// The eight points surrounding this one.
+    pub fn neighbors(&self) -> impl Iterator<Item = Point> {
+        let p = *self;
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| p.offset(dx, dy)))
+            .filter(move |&n| n != p)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        self.offset(rhs.x, rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        self.offset(-rhs.x, -rhs.y)
+    }
+}
+
+/// Inclusive bounding box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BBox {
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+}
+
+/// A sparse grid that only stores occupied cells.
+///
+/// The bounding box of the occupied cells is kept up to date on every insert and remove by
+/// counting how many cells sit in each row and column, so querying it never scans the grid.
+#[derive(Debug, Clone)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+    cols: BTreeMap<isize, usize>,
+    rows: BTreeMap<isize, usize>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            cols: BTreeMap::new(),
+            rows: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        self.cells.contains_key(p)
+    }
+
+    pub fn get(&self, p: &Point) -> Option<&T> {
+        self.cells.get(p)
+    }
+
+    pub fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
+        self.cells.get_mut(p)
+    }
+
+    pub fn insert(&mut self, p: Point, value: T) -> Option<T> {
+        let old = self.cells.insert(p, value);
+        if old.is_none() {
+            *self.cols.entry(p.x).or_default() += 1;
+            *self.rows.entry(p.y).or_default() += 1;
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, p: &Point) -> Option<T> {
+        let old = self.cells.remove(p);
+        if old.is_some() {
+            decrement(&mut self.cols, p.x);
+            decrement(&mut self.rows, p.y);
+        }
+
+        old
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, Point, T> {
+        self.cells.iter()
+    }
+
+    pub fn points(&self) -> hash_map::Keys<'_, Point, T> {
+        self.cells.keys()
+    }
+
+    pub fn values(&self) -> hash_map::Values<'_, Point, T> {
+        self.cells.values()
+    }
+
+    /// Bounding box of all occupied cells, or `None` if the grid is empty.
+    pub fn bbox(&self) -> Option<BBox> {
+        let (&min_x, _) = self.cols.first_key_value()?;
+        let (&max_x, _) = self.cols.last_key_value()?;
+        let (&min_y, _) = self.rows.first_key_value()?;
+        let (&max_y, _) = self.rows.last_key_value()?;
+
+        Some(BBox {
+            min: Point::new(min_x, min_y),
+            max: Point::new(max_x, max_y),
+        })
+    }
+
+    /// Number of unoccupied cells within the bounding box.
+    pub fn count_empty(&self) -> usize {
+        self.bbox().map_or(0, |bbox| bbox.area() - self.len())
+    }
+
+    /// Renders the bounding box one row per line, mapping each cell to a char with `cell`.
+    pub fn render(&self, cell: impl Fn(Option<&T>) -> char) -> String {
+        match self.bbox() {
+            None => String::new(),
+            Some(bbox) => self.render_within(&bbox, cell),
+        }
+    }
+
+    /// Like [`SparseGrid::render`], but over an explicit region.
+    pub fn render_within(&self, bbox: &BBox, cell: impl Fn(Option<&T>) -> char) -> String {
+        let mut out = String::with_capacity((bbox.width() + 1) * bbox.height());
+
+        for y in bbox.min.y..=bbox.max.y {
+            if y != bbox.min.y {
+                out.push('\n');
+            }
+
+            for x in bbox.min.x..=bbox.max.x {
+                out.push(cell(self.get(&Point::new(x, y))));
+            }
+        }
+
+        out
+    }
+}
+
+fn decrement(counts: &mut BTreeMap<isize, usize>, k: isize) {
+    if let Some(n) = counts.get_mut(&k) {
+        *n -= 1;
+        if *n == 0 {
+            counts.remove(&k);
+        }
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        let mut grid = Self::new();
+        for (p, v) in iter {
+            grid.insert(p, v);
+        }
+
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bbox_tracks_inserts_and_removes() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bbox(), None);
+
+        grid.insert(Point::new(3, 4), ());
+        grid.insert(Point::new(-2, 7), ());
+        grid.insert(Point::new(5, -1), ());
+        assert_eq!(
+            grid.bbox(),
+            Some(BBox {
+                min: Point::new(-2, -1),
+                max: Point::new(5, 7),
+            })
+        );
+
+        grid.remove(&Point::new(-2, 7));
+        assert_eq!(
+            grid.bbox(),
+            Some(BBox {
+                min: Point::new(3, -1),
+                max: Point::new(5, 4),
+            })
+        );
+    }
+
+    #[test]
+    fn test_bbox_excludes_origin() {
+        let grid: SparseGrid<()> = [(Point::new(10, 10), ()), (Point::new(11, 12), ())]
+            .into_iter()
+            .collect();
+
+        assert_eq!(grid.bbox().unwrap().min, Point::new(10, 10));
+        assert_eq!(grid.count_empty(), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let grid: SparseGrid<char> = [(Point::new(-1, 0), 'a'), (Point::new(1, 1), 'b')]
+            .into_iter()
+            .collect();
+
+        assert_eq!(grid.render(|c| *c.unwrap_or(&'.')), "a..\n..b");
+    }
+}
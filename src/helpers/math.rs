@@ -0,0 +1,161 @@
+//! Number theory helpers.
+//!
+//! Modular arithmetic is done in 128 bits internally, so any `i64`/`u64` modulus is safe.
+
+use std::ops::{Div, Mul, Rem};
+
+pub fn gcd<T>(mut a: T, mut b: T) -> T
+where
+    T: Copy + PartialEq + Rem<Output = T> + From<u8>,
+{
+    while b != T::from(0) {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+pub fn lcm<T>(a: T, b: T) -> T
+where
+    T: Copy + PartialEq + Rem<Output = T> + Div<Output = T> + Mul<Output = T> + From<u8>,
+{
+    if a == T::from(0) || b == T::from(0) {
+        T::from(0)
+    } else {
+        a / gcd(a, b) * b
+    }
+}
+
+/// The gcd of all numbers in `it`, or 0 if it is empty.
+pub fn gcd_all<T, I>(it: I) -> T
+where
+    T: Copy + PartialEq + Rem<Output = T> + From<u8>,
+    I: IntoIterator<Item = T>,
+{
+    it.into_iter().fold(T::from(0), gcd)
+}
+
+/// The lcm of all numbers in `it`, or 1 if it is empty.
+pub fn lcm_all<T, I>(it: I) -> T
+where
+    T: Copy + PartialEq + Rem<Output = T> + Div<Output = T> + Mul<Output = T> + From<u8>,
+    I: IntoIterator<Item = T>,
+{
+    it.into_iter().fold(T::from(1), lcm)
+}
+
+/// Extended Euclidean algorithm: returns `(g, x, y)` with `a * x + b * y == g == gcd(a, b)`.
+pub fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `base.pow(exp) % m`.
+pub fn mod_pow(base: u64, mut exp: u64, m: u64) -> u64 {
+    let m = m as u128;
+    let mut base = base as u128 % m;
+    let mut result = 1 % m;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+
+        base = base * base % m;
+        exp >>= 1;
+    }
+
+    result as u64
+}
+
+/// The inverse of `a` modulo `m`, if `a` and `m` are coprime.
+pub fn mod_inv(a: i64, m: i64) -> Option<i64> {
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+/// Solves the system `x ≡ r (mod m)` for every `(r, m)` in `congruences`.
+///
+/// The moduli need not be coprime. Returns `(x, lcm of all moduli)` with `x` the smallest
+/// non-negative solution, or `None` if the system is inconsistent.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut x: i128 = 0;
+    let mut m: i128 = 1;
+
+    for &(r, n) in congruences {
+        let (r, n) = (r as i128, n as i128);
+        let (g, p, _) = ext_gcd(m as i64, n as i64);
+        let g = g as i128;
+
+        let diff = r - x;
+        if diff % g != 0 {
+            return None;
+        }
+
+        let step = n / g;
+        let k = (diff / g % step * p as i128).rem_euclid(step);
+
+        x += m * k;
+        m *= step;
+        x = x.rem_euclid(m);
+    }
+
+    Some((x as i64, m as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12u64, 18), 6);
+        assert_eq!(lcm(4usize, 6), 12);
+        assert_eq!(lcm(0i64, 6), 0);
+        assert_eq!(gcd_all([12u32, 18, 27]), 3);
+        assert_eq!(lcm_all([2u64, 3, 4, 6]), 12);
+        assert_eq!(lcm_all(Vec::<u64>::new()), 1);
+    }
+
+    #[test]
+    fn test_ext_gcd() {
+        let (g, x, y) = ext_gcd(240, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+    }
+
+    #[test]
+    fn test_mod_pow_inv() {
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(u64::MAX, 2, u64::MAX - 1), 1);
+        assert_eq!(mod_inv(3, 11), Some(4));
+        assert_eq!(mod_inv(-3, 11), Some(7));
+        assert_eq!(mod_inv(6, 9), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt(&[(0, 4), (1, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+    }
+}
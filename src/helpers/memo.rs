@@ -0,0 +1,205 @@
+//! Memoization for recursive searches.
+//!
+//! A [`Memo`] caches results keyed on the arguments of a recursive function. The function takes
+//! the memo as a parameter and routes its recursive calls through [`Memo::get_or_insert_with`]:
+//!
+//! ```
+//! use advent_of_code::helpers::memo::Memo;
+//!
+//! fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+//!     memo.get_or_insert_with(n, |memo| match n {
+//!         0 | 1 => n,
+//!         _ => fib(memo, n - 1) + fib(memo, n - 2),
+//!     })
+//! }
+//!
+//! assert_eq!(fib(&mut Memo::new(), 90), 2880067194370816120);
+//! ```
+//!
+//! When a memo is dropped its statistics are collected, and `solve!` prints them after the result.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    hash::Hash,
+    sync::Mutex,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl MemoStats {
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+impl std::ops::AddAssign for MemoStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.hits += rhs.hits;
+        self.misses += rhs.misses;
+        self.evictions += rhs.evictions;
+    }
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memo: {} hits, {} misses ({:.1}% hit rate)",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0
+        )?;
+
+        if self.evictions > 0 {
+            write!(f, ", {} evictions", self.evictions)?;
+        }
+
+        Ok(())
+    }
+}
+
+static COLLECTED: Mutex<Option<MemoStats>> = Mutex::new(None);
+
+/// Takes the combined statistics of all memos dropped since the last call.
+pub fn take_stats() -> Option<MemoStats> {
+    COLLECTED.lock().unwrap().take()
+}
+
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    capacity: Option<usize>,
+    order: VecDeque<K>,
+    stats: MemoStats,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(None)
+    }
+
+    /// A memo holding at most `capacity` results. The oldest result is evicted first.
+    pub fn bounded(capacity: usize) -> Self {
+        Self::with_capacity(Some(capacity))
+    }
+
+    fn with_capacity(capacity: Option<usize>) -> Self {
+        Self {
+            cache: HashMap::new(),
+            capacity,
+            order: VecDeque::new(),
+            stats: MemoStats::default(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    /// The cached result for `key`, computing it with `f` on a miss.
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(v) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return v.clone();
+        }
+
+        self.stats.misses += 1;
+        let v = f(self);
+        self.insert(key, v.clone());
+
+        v
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == Some(0) {
+            return;
+        }
+
+        let is_new = self.cache.insert(key.clone(), value).is_none();
+        if let (true, Some(capacity)) = (is_new, self.capacity) {
+            self.order.push_back(key);
+
+            while self.cache.len() > capacity {
+                let oldest = self.order.pop_front().unwrap();
+                self.cache.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.stats
+    }
+}
+
+impl<K, V> Drop for Memo<K, V> {
+    fn drop(&mut self) {
+        if self.stats.hits + self.stats.misses > 0 {
+            if let Ok(mut collected) = COLLECTED.lock() {
+                *collected.get_or_insert_with(MemoStats::default) += self.stats;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(memo: &mut Memo<(u32, u32), u64>, x: u32, y: u32) -> u64 {
+        memo.get_or_insert_with((x, y), |memo| {
+            if x == 0 || y == 0 {
+                1
+            } else {
+                paths(memo, x - 1, y) + paths(memo, x, y - 1)
+            }
+        })
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut memo = Memo::new();
+        assert_eq!(paths(&mut memo, 16, 16), 601080390);
+
+        let stats = memo.stats();
+        assert_eq!(stats.misses as usize, memo.len());
+        assert_eq!(stats.misses, 17 * 17 - 1);
+        assert_eq!(stats.evictions, 0);
+        assert!(stats.hits > 0);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut memo = Memo::bounded(8);
+        assert_eq!(paths(&mut memo, 10, 10), 184756);
+        assert_eq!(memo.len(), 8);
+        assert!(memo.stats().evictions > 0);
+
+        let mut memo = Memo::bounded(0);
+        assert_eq!(paths(&mut memo, 5, 5), 252);
+        assert!(memo.is_empty());
+        assert_eq!(memo.stats().hits, 0);
+    }
+}
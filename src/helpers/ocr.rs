@@ -0,0 +1,225 @@
+//! Recognizes the block-letter font that Advent of Code draws answers in.
+//!
+//! Two sizes are known: the common 6 pixel tall font (letters 4 pixels wide) and the 10 pixel tall
+//! font (letters 6 pixels wide). Letters are separated by at least one blank column.
+
+use std::collections::HashMap;
+
+const FONT_6: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const FONT_10: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+type Glyph = Vec<Vec<bool>>;
+
+fn parse_glyph(s: &str) -> Glyph {
+    trim_columns(
+        &s.lines()
+            .map(|l| l.chars().map(|c| c == '#').collect())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Drops blank columns on either side of `pixels`.
+fn trim_columns(pixels: &[Vec<bool>]) -> Glyph {
+    let width = pixels.iter().map(Vec::len).max().unwrap_or(0);
+    let lit = |c: usize| pixels.iter().any(|row| row.get(c) == Some(&true));
+
+    match (0..width).position(lit) {
+        None => vec![vec![]; pixels.len()],
+        Some(start) => {
+            let end = (0..width).rposition(lit).unwrap() + 1;
+            pixels
+                .iter()
+                .map(|row| (start..end).map(|c| row.get(c) == Some(&true)).collect())
+                .collect()
+        }
+    }
+}
+
+fn font(height: usize) -> Option<HashMap<Glyph, char>> {
+    let glyphs: &[(char, &str)] = match height {
+        6 => &FONT_6,
+        10 => &FONT_10,
+        _ => return None,
+    };
+
+    Some(glyphs.iter().map(|&(c, s)| (parse_glyph(s), c)).collect())
+}
+
+/// Reads the letters drawn in `pixels`, given as rows of lit (`true`) and dark pixels.
+///
+/// Blank rows above and below the letters are ignored. Fails if the remaining height does not
+/// match a known font or if any glyph is not a letter of that font.
+pub fn recognize(pixels: &[Vec<bool>]) -> Result<String, String> {
+    let row_lit = |r: &Vec<bool>| r.iter().any(|&p| p);
+    let top = pixels
+        .iter()
+        .position(row_lit)
+        .ok_or("nothing to recognize")?;
+    let bottom = pixels.iter().rposition(row_lit).unwrap() + 1;
+    let pixels = &pixels[top..bottom];
+
+    let height = pixels.len();
+    let font = font(height).ok_or(format!("no font is {height} pixels tall"))?;
+
+    let width = pixels.iter().map(Vec::len).max().unwrap_or(0);
+    let col_lit = |c: usize| pixels.iter().any(|row| row.get(c) == Some(&true));
+
+    let mut letters = String::new();
+    let mut c = 0;
+    while c < width {
+        if !col_lit(c) {
+            c += 1;
+            continue;
+        }
+
+        let start = c;
+        while c < width && col_lit(c) {
+            c += 1;
+        }
+
+        let glyph = pixels
+            .iter()
+            .map(|row| (start..c).map(|i| row.get(i) == Some(&true)).collect())
+            .collect::<Glyph>();
+
+        match font.get(&glyph) {
+            Some(&letter) => letters.push(letter),
+            None => return Err(format!("unknown glyph in columns {start}..{c}")),
+        }
+    }
+
+    Ok(letters)
+}
+
+/// Like [`recognize`], but reads pixels from text where `lit` marks a lit pixel.
+pub fn recognize_str(s: &str, lit: char) -> Result<String, String> {
+    let pixels = s
+        .lines()
+        .map(|l| l.chars().map(|c| c == lit).collect())
+        .collect::<Vec<_>>();
+
+    recognize(&pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize_small() {
+        let s = "\
+###..#.....##..####.#..#..##..####..##..
+#..#.#....#..#.#....#.#..#..#....#.#..#.
+#..#.#....#....###..##...#..#...#..#....
+###..#....#.##.#....#.#..####..#...#....
+#....#....#..#.#....#.#..#..#.#....#..#.
+#....####..###.#....#..#.#..#.####..##..";
+
+        assert_eq!(recognize_str(s, '#'), Ok("PLGFKAZC".to_owned()));
+    }
+
+    #[test]
+    fn test_recognize_large() {
+        let s = "\n\
+#....#..######\n\
+#....#..#.....\n\
+.#..#...#.....\n\
+.#..#...#.....\n\
+..##....#####.\n\
+..##....#.....\n\
+.#..#...#.....\n\
+.#..#...#.....\n\
+#....#..#.....\n\
+#....#..######\n";
+
+        assert_eq!(recognize_str(s, '#'), Ok("XE".to_owned()));
+    }
+
+    #[test]
+    fn test_recognize_unknown() {
+        assert!(recognize_str("#.#\n.#.\n#.#\n.#.\n#.#\n.#.", '#').is_err());
+        assert!(recognize_str("###\n###", '#').is_err());
+        assert!(recognize_str("...", '#').is_err());
+    }
+}
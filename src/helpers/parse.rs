@@ -0,0 +1,386 @@
+//! A small cursor-based toolkit for parsing puzzle input.
+//!
+//! A [`Cursor`] always remembers the full input it was created from, so cursors handed out for
+//! single lines or blocks still report errors at their line and column in the whole input.
+
+use std::{fmt, str::FromStr};
+
+use ndarray::Array2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            end: src.len(),
+        }
+    }
+
+    /// The unconsumed input.
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.pos..self.end]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.end
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// An error located at the current position.
+    pub fn error(&self, msg: impl Into<String>) -> ParseError {
+        self.error_at(self.pos, msg)
+    }
+
+    fn error_at(&self, pos: usize, msg: impl Into<String>) -> ParseError {
+        let before = &self.src[..pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            col: before[line_start..].chars().count() + 1,
+            msg: msg.into(),
+        }
+    }
+
+    fn found(&self) -> String {
+        match self.rest().lines().next() {
+            None | Some("") => "end of input".to_owned(),
+            Some(l) => format!("{:?}", l.chars().take(16).collect::<String>()),
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let n = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += n;
+
+        &rest[..n]
+    }
+
+    /// Consumes `tag` if the input starts with it.
+    pub fn eat(&mut self, tag: &str) -> bool {
+        if self.rest().starts_with(tag) {
+            self.pos += tag.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes `tag`, which must come next.
+    pub fn tag(&mut self, tag: &str) -> Result<()> {
+        if self.eat(tag) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {tag:?}, found {}", self.found())))
+        }
+    }
+
+    pub fn skip_ws(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    /// A non-empty run of ASCII letters, digits and underscores.
+    pub fn word(&mut self) -> Result<&'a str> {
+        let w = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if w.is_empty() {
+            Err(self.error(format!("expected a word, found {}", self.found())))
+        } else {
+            Ok(w)
+        }
+    }
+
+    /// A non-empty run of non-whitespace characters.
+    pub fn token(&mut self) -> Result<&'a str> {
+        let t = self.take_while(|c| !c.is_whitespace());
+        if t.is_empty() {
+            Err(self.error(format!("expected a token, found {}", self.found())))
+        } else {
+            Ok(t)
+        }
+    }
+
+    /// Everything up to, but not including, `delim`. The delimiter itself is consumed.
+    pub fn take_until(&mut self, delim: &str) -> Result<&'a str> {
+        match self.rest().find(delim) {
+            None => Err(self.error(format!("expected {delim:?} somewhere ahead"))),
+            Some(n) => {
+                let s = &self.rest()[..n];
+                self.pos += n + delim.len();
+                Ok(s)
+            }
+        }
+    }
+
+    /// Parses the text consumed by `take` with [`FromStr`].
+    pub fn parse<T: FromStr>(
+        &mut self,
+        take: impl FnOnce(&mut Self) -> Result<&'a str>,
+    ) -> Result<T> {
+        let start = self.pos;
+        let s = take(self)?;
+
+        s.parse()
+            .map_err(|_| self.error_at(start, format!("cannot parse {s:?}")))
+    }
+
+    /// An integer with an optional leading sign.
+    pub fn int<T: FromStr>(&mut self) -> Result<T> {
+        self.parse(|c| {
+            let start = c.pos;
+            if !c.eat("-") {
+                c.eat("+");
+            }
+
+            if c.take_while(|ch| ch.is_ascii_digit()).is_empty() {
+                c.pos = start;
+                return Err(c.error(format!("expected an integer, found {}", c.found())));
+            }
+
+            Ok(&c.src[start..c.pos])
+        })
+    }
+
+    /// One or more items separated by `sep`.
+    pub fn list<T>(
+        &mut self,
+        sep: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.eat(sep) {
+            items.push(item(self)?);
+        }
+
+        Ok(items)
+    }
+
+    /// Splits at the first `sep`, returning the key and a cursor over the value.
+    pub fn key_value(&mut self, sep: &str) -> Result<(&'a str, Cursor<'a>)> {
+        let key = self.take_until(sep)?;
+        let value = Cursor {
+            src: self.src,
+            pos: self.pos,
+            end: self.end,
+        };
+        self.pos = self.end;
+
+        Ok((key, value))
+    }
+
+    /// The next line, without its line break.
+    pub fn line(&mut self) -> Option<Cursor<'a>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let rest = self.rest();
+        let (len, next) = match rest.find('\n') {
+            Some(n) => (n, self.pos + n + 1),
+            None => (rest.len(), self.end),
+        };
+        let len = if rest[..len].ends_with('\r') {
+            len - 1
+        } else {
+            len
+        };
+
+        let line = Cursor {
+            src: self.src,
+            pos: self.pos,
+            end: self.pos + len,
+        };
+        self.pos = next;
+
+        Some(line)
+    }
+
+    pub fn lines(&mut self) -> impl Iterator<Item = Cursor<'a>> + '_ {
+        std::iter::from_fn(move || self.line())
+    }
+
+    /// The next run of non-blank lines. Leading blank lines are skipped.
+    pub fn block(&mut self) -> Option<Cursor<'a>> {
+        let mut block: Option<Cursor<'a>> = None;
+
+        while let Some(l) = self.line() {
+            match (&mut block, l.is_empty()) {
+                (None, true) => continue,
+                (Some(_), true) => break,
+                (None, false) => block = Some(l),
+                (Some(b), false) => b.end = l.end,
+            }
+        }
+
+        block
+    }
+
+    pub fn blocks(&mut self) -> impl Iterator<Item = Cursor<'a>> + '_ {
+        std::iter::from_fn(move || self.block())
+    }
+
+    /// Reads the remaining lines as a rectangular grid, mapping each char with `cell`.
+    pub fn grid<T>(&mut self, mut cell: impl FnMut(char) -> Option<T>) -> Result<Array2<T>> {
+        let mut cells = vec![];
+        let mut width = None;
+        let mut height = 0;
+
+        while let Some(mut l) = self.line() {
+            let row_width = l.rest().chars().count();
+            if *width.get_or_insert(row_width) != row_width {
+                return Err(l.error(format!(
+                    "expected a row of width {}, found width {row_width}",
+                    width.unwrap()
+                )));
+            }
+
+            while let Some(c) = l.peek() {
+                match cell(c) {
+                    Some(v) => cells.push(v),
+                    None => return Err(l.error(format!("unexpected {c:?}"))),
+                }
+                l.pos += c.len_utf8();
+            }
+
+            height += 1;
+        }
+
+        Ok(Array2::from_shape_vec((height, width.unwrap_or(0)), cells).unwrap())
+    }
+
+    /// Fails unless all input has been consumed.
+    pub fn end(&self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(format!("unexpected {}", self.found())))
+        }
+    }
+}
+
+/// Parses every line of `input` with `f`, which must consume the whole line.
+pub fn each_line<'a, T>(
+    input: &'a str,
+    mut f: impl FnMut(&mut Cursor<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut c = Cursor::new(input);
+    c.lines()
+        .map(|mut l| {
+            let v = f(&mut l)?;
+            l.end()?;
+            Ok(v)
+        })
+        .collect()
+}
+
+/// Parses every blank-line-separated block of `input` with `f`, which must consume the whole block.
+pub fn each_block<'a, T>(
+    input: &'a str,
+    mut f: impl FnMut(&mut Cursor<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut c = Cursor::new(input);
+    c.blocks()
+        .map(|mut b| {
+            let v = f(&mut b)?;
+            b.end()?;
+            Ok(v)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ints_and_lists() {
+        let mut c = Cursor::new("-12, +3, 4 rest");
+        assert_eq!(c.list(", ", Cursor::int::<i32>), Ok(vec![-12, 3, 4]));
+        assert_eq!(c.rest(), " rest");
+
+        let mut c = Cursor::new("300");
+        assert!(c.int::<u8>().is_err());
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = "1-2\n3-4\n5+6";
+        let err = each_line(input, |l| {
+            let a: u32 = l.int()?;
+            l.tag("-")?;
+            let b: u32 = l.int()?;
+            Ok((a, b))
+        })
+        .unwrap_err();
+
+        assert_eq!((err.line, err.col), (3, 2));
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 2: expected \"-\", found \"+6\""
+        );
+    }
+
+    #[test]
+    fn test_blocks_and_key_value() {
+        let input = "\na: 1\nb: 2\n\n\nc: -3\n";
+        let blocks = each_block(input, |b| {
+            b.lines()
+                .map(|mut l| {
+                    let (k, mut v) = l.key_value(": ")?;
+                    let n: i32 = v.int()?;
+                    v.end()?;
+                    Ok((k, n))
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .unwrap();
+
+        assert_eq!(blocks, vec![vec![("a", 1), ("b", 2)], vec![("c", -3)]]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut c = Cursor::new("#.\n.#\n");
+        let g = c.grid(|ch| Some(ch == '#')).unwrap();
+        assert_eq!(g, ndarray::arr2(&[[true, false], [false, true]]));
+
+        let err = Cursor::new("#.\n.#.")
+            .grid(|ch| Some(ch == '#'))
+            .unwrap_err();
+        assert_eq!((err.line, err.col), (2, 1));
+
+        let err = Cursor::new("#.\n.x").grid(|ch| match ch {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        });
+        assert_eq!(err.map_err(|e| (e.line, e.col)), Err((2, 2)));
+    }
+}
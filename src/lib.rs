@@ -34,6 +34,10 @@ macro_rules! solve {
                     println!("not solved.")
                 }
             }
+
+            if let Some(stats) = advent_of_code::helpers::memo::take_stats() {
+                println!("{}{}{}", ANSI_ITALIC, stats, ANSI_RESET);
+            }
         }
 
         println!("🎄 {}Part {}{} 🎄", ANSI_BOLD, $part, ANSI_RESET);